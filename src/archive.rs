@@ -0,0 +1,333 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::clock::Clock;
+
+/// manifest written at root of archive, not part of archived files, so sources must not contain it
+pub const MANIFEST: &str = "manifest.json";
+
+#[derive(Debug, Default)]
+pub struct ArchiveOption {
+    /// mark archived files and manifest read-only
+    pub read_only: bool,
+}
+
+/// an archived file, path is relative to archive with `/` separators
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub path: String,
+    /// SHA-256 of file content in lower case hex
+    pub sha256: String,
+}
+
+/// ## files of an archive with their checksums, saved as `manifest.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// time archive is created given by clock, in RFC 3339
+    pub created: String,
+    /// purpose folder archived
+    pub source: String,
+    pub files: Vec<ManifestEntry>,
+    /// empty folders, path is relative to archive with `/` separators
+    #[serde(default)]
+    pub directories: Vec<String>,
+}
+
+/// ## archive compared with its manifest, paths are relative to archive
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ArchiveVerification {
+    /// files and empty folders of manifest no longer in archive
+    pub missing: Vec<String>,
+    /// files whose checksum differs from manifest
+    pub modified: Vec<String>,
+    /// files and empty folders in archive not listed in manifest
+    pub unexpected: Vec<String>,
+}
+
+impl ArchiveVerification {
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// ## copy a completed purpose folder, such as `stats/CSR`, into archive with a manifest
+///
+/// archive is filled in a folder next to dest and moved into place once every file is copied,
+/// so a failed archive leaves nothing behind
+///
+/// ### Arguments
+///
+/// @ source: &Path - purpose folder to freeze
+///
+/// @ dest: &Path - archive location, must not exist or be empty
+///
+/// @ option: &ArchiveOption - whether to mark files read-only
+///
+/// @ clock: &dyn Clock - provides creation time
+pub fn archive(
+    source: &Path,
+    dest: &Path,
+    option: &ArchiveOption,
+    clock: &dyn Clock,
+) -> anyhow::Result<ArchiveManifest> {
+    if !source.is_dir() {
+        return Err(anyhow!("Purpose folder {} not found", source.display()));
+    }
+    if source.join(MANIFEST).exists() {
+        return Err(anyhow!(
+            "Purpose folder {} contains {}, which is reserved for manifest",
+            source.display(),
+            MANIFEST
+        ));
+    }
+    if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+        return Err(anyhow!("Archive {} already exists", dest.display()));
+    }
+    let name = dest
+        .file_name()
+        .ok_or_else(|| anyhow!("Archive {} has no folder name", dest.display()))?;
+    let staging = dest.with_file_name(format!(
+        ".{}.archiving-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    fs::remove_dir_all(&staging).ok();
+    let manifest = fill(source, &staging, clock).and_then(|manifest| {
+        if dest.exists() {
+            fs::remove_dir(dest)?;
+        }
+        fs::rename(&staging, dest)?;
+        Ok(manifest)
+    });
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            fs::remove_dir_all(&staging).ok();
+            return Err(e);
+        }
+    };
+    if option.read_only {
+        for entry in &manifest.files {
+            lock(&dest.join(&entry.path))?;
+        }
+        lock(&dest.join(MANIFEST))?;
+    }
+    Ok(manifest)
+}
+
+/// copy files and empty folders of source into staging and write manifest there
+fn fill(source: &Path, staging: &Path, clock: &dyn Clock) -> anyhow::Result<ArchiveManifest> {
+    let mut files = vec![];
+    let mut directories = vec![];
+    collect(source, Path::new(""), &mut files, &mut directories)?;
+    let mut manifest = ArchiveManifest {
+        created: clock.now().to_rfc3339(),
+        source: source.to_string_lossy().to_string(),
+        files: vec![],
+        directories: directories.iter().map(|dir| portable(dir)).collect(),
+    };
+    fs::create_dir_all(staging)?;
+    for dir in directories {
+        fs::create_dir_all(staging.join(dir))?;
+    }
+    for file in files {
+        let target = staging.join(&file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let original = source.join(&file);
+        let sha256 = checksum(&original)?;
+        fs::copy(&original, &target)?;
+        if checksum(&target)? != sha256 {
+            return Err(anyhow!(
+                "Copy of {} differs from source",
+                original.display()
+            ));
+        }
+        manifest.files.push(ManifestEntry {
+            path: portable(&file),
+            sha256,
+        });
+    }
+    fs::write(
+        staging.join(MANIFEST),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+/// ## check archive against its manifest to detect tampering
+pub fn verify_archive(archive: &Path) -> anyhow::Result<ArchiveVerification> {
+    let manifest: ArchiveManifest = serde_json::from_slice(
+        &fs::read(archive.join(MANIFEST))
+            .map_err(|e| anyhow!("Manifest of {} not readable: {}", archive.display(), e))?,
+    )?;
+    let mut files = vec![];
+    let mut directories = vec![];
+    collect(archive, Path::new(""), &mut files, &mut directories)?;
+    let mut actual = files
+        .iter()
+        .map(|file| (portable(file), file))
+        .filter(|(path, _)| path != MANIFEST)
+        .collect::<BTreeMap<String, &PathBuf>>();
+    let mut verification = ArchiveVerification::default();
+    for entry in &manifest.files {
+        match actual.remove(&entry.path) {
+            Some(file) => {
+                if checksum(&archive.join(file))? != entry.sha256 {
+                    verification.modified.push(entry.path.clone());
+                }
+            }
+            None => verification.missing.push(entry.path.clone()),
+        }
+    }
+    verification.unexpected = actual.into_keys().collect();
+    let mut actual = directories
+        .iter()
+        .map(|dir| portable(dir))
+        .collect::<Vec<String>>();
+    for dir in &manifest.directories {
+        match actual.iter().position(|actual| actual == dir) {
+            Some(index) => {
+                actual.remove(index);
+            }
+            None => verification.missing.push(dir.clone()),
+        }
+    }
+    verification.unexpected.append(&mut actual);
+    Ok(verification)
+}
+
+/// collect files and empty folders below dir in name order, relative to root
+fn collect(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    empty: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let mut entries = fs::read_dir(root.join(dir))?.collect::<Result<Vec<_>, _>>()?;
+    if entries.is_empty() && !dir.as_os_str().is_empty() {
+        empty.push(dir.to_path_buf());
+    }
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let child = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect(root, &child, files, empty)?;
+        } else {
+            files.push(child);
+        }
+    }
+    Ok(())
+}
+
+fn checksum(path: &Path) -> anyhow::Result<String> {
+    Ok(format!("{:x}", Sha256::digest(fs::read(path)?)))
+}
+
+/// relative path with `/` separators, so manifest verifies on Linux and Windows
+fn portable(path: &Path) -> String {
+    path.iter()
+        .map(|segment| segment.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn lock(path: &Path) -> anyhow::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[test]
+    fn archive_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let csr = root.path().join("stats").join("CSR");
+        let program = csr.join("product").join("program");
+        fs::create_dir_all(&program)?;
+        fs::create_dir_all(csr.join("product").join("output"))?;
+        fs::write(program.join("ae.sas"), "data ae; run;")?;
+        fs::write(csr.join("readme.txt"), "final")?;
+        let dest = root.path().join("archive").join("CSR");
+
+        let clock = FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap());
+        let manifest = archive(&csr, &dest, &ArchiveOption { read_only: true }, &clock)?;
+        assert_eq!("2024-03-05T00:00:00+00:00", manifest.created);
+        let paths = manifest
+            .files
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(vec!["product/program/ae.sas", "readme.txt"], paths);
+        assert_eq!(vec!["product/output"], manifest.directories);
+        assert!(dest.join("product").join("output").is_dir());
+        assert!(fs::metadata(dest.join("readme.txt"))?
+            .permissions()
+            .readonly());
+        assert!(verify_archive(&dest)?.passed());
+        assert!(archive(&csr, &dest, &ArchiveOption::default(), &clock).is_err());
+        fs::write(csr.join(MANIFEST), "{}")?;
+        let other = root.path().join("archive").join("other");
+        assert!(archive(&csr, &other, &ArchiveOption::default(), &clock).is_err());
+        assert!(!other.exists());
+
+        let ae = dest.join("product").join("program").join("ae.sas");
+        let mut permissions = fs::metadata(&ae)?.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&ae, permissions)?;
+        fs::write(&ae, "data ae; set raw.ae; run;")?;
+        fs::remove_file(dest.join("readme.txt"))?;
+        fs::write(dest.join("product").join("notes.txt"), "")?;
+        fs::remove_dir(dest.join("product").join("output"))?;
+        fs::create_dir(dest.join("product").join("tmp"))?;
+        assert_eq!(
+            ArchiveVerification {
+                missing: vec!["readme.txt".into(), "product/output".into()],
+                modified: vec!["product/program/ae.sas".into()],
+                unexpected: vec!["product/notes.txt".into(), "product/tmp".into()],
+            },
+            verify_archive(&dest)?
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn archive_failure_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let csr = root.path().join("CSR");
+        fs::create_dir_all(&csr)?;
+        fs::write(csr.join("ae.sas"), "data ae; run;")?;
+        // unreadable after ae.sas is copied
+        let broken = csr.join("zz.sas");
+        std::os::unix::fs::symlink(root.path().join("gone.sas"), &broken)?;
+        let archives = root.path().join("archive");
+        let dest = archives.join("CSR");
+        fs::create_dir_all(&dest)?;
+        let clock = FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap());
+
+        assert!(archive(&csr, &dest, &ArchiveOption::default(), &clock).is_err());
+        assert!(fs::read_dir(&dest)?.next().is_none());
+        assert_eq!(1, fs::read_dir(&archives)?.count());
+        fs::remove_file(&broken)?;
+        archive(&csr, &dest, &ArchiveOption::default(), &clock)?;
+        assert!(verify_archive(&dest)?.passed());
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    engine::Engine,
+    generator::StudyMetadata,
+    reader::DiscoveryProfile,
+    skeleton::{Builder, DocumentSkeleton, SkeletonDiff, SkeletonFormat, StatSkeleton},
+};
+
+/// folder of production area in stat skeleton, qc programs are misplaced there
+const PRODUCT: &str = "product";
+const QC_PREFIX: &str = "v-";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum ComplianceKind {
+    MissingFolder,
+    NonStandardFolder,
+    /// qc program such as `v-ae.sas` in product folder
+    MisplacedProgram,
+    /// skeleton folder not checked as a placeholder such as `{delivery}` has no value
+    UnresolvedPlaceholder,
+}
+
+/// a finding of audit, path is relative to trial directory
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ComplianceIssue {
+    /// trial id, such as `ak101-202`
+    pub trial: String,
+    /// purpose of stat skeleton, none for documents
+    pub purpose: Option<String>,
+    pub kind: ComplianceKind,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ComplianceReport {
+    /// trials and purposes audited, such as `ak101-202` and `ak101-202-CSR`
+    pub audited: Vec<String>,
+    pub issues: Vec<ComplianceIssue>,
+}
+
+impl ComplianceReport {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+    fn record(
+        &mut self,
+        trial: &str,
+        purpose: Option<&str>,
+        (diff, unresolved): (SkeletonDiff, Vec<PathBuf>),
+    ) {
+        let missing = diff
+            .to_create
+            .into_iter()
+            .map(|path| (ComplianceKind::MissingFolder, path));
+        let non_standard = diff
+            .non_standard
+            .into_iter()
+            .map(|path| (ComplianceKind::NonStandardFolder, path));
+        let unresolved = unresolved
+            .into_iter()
+            .map(|path| (ComplianceKind::UnresolvedPlaceholder, path));
+        for (kind, path) in missing.chain(non_standard).chain(unresolved) {
+            self.issues.push(ComplianceIssue {
+                trial: trial.into(),
+                purpose: purpose.map(|purpose| purpose.into()),
+                kind,
+                path,
+            });
+        }
+    }
+}
+
+/// ## how audit finds trials and reads skeletons
+///
+/// trials and purposes are audited in directories `discovery` finds them,
+/// so stat skeletons are rooted at levels of layout between `{trial}` and `{purpose}`
+#[derive(Debug, Clone, Default)]
+pub struct AuditOption {
+    /// how products, trials and purposes are found below root
+    pub discovery: DiscoveryProfile,
+    /// format of document and stat skeleton definitions
+    pub format: SkeletonFormat,
+    /// regex of purpose names with type of purposes matching, such as `^DMC\d+$` and `dmc`,
+    /// first match wins, purposes not matched are their own type
+    pub purpose_types: Vec<(String, String)>,
+    /// study metadata node conditions are checked against by trial id, such as `ak101-202`
+    pub studies: HashMap<String, StudyMetadata>,
+}
+
+impl AuditOption {
+    /// ## audit every trial and purpose `discovery` finds against standard skeletons
+    ///
+    /// ### Arguments
+    ///
+    /// @ root: &Path - root of studies, same as `DiscoveryProfile::list_projects`
+    ///
+    /// @ documents: &[u8] - document skeleton in `format`, checked once per trial
+    ///
+    /// @ stat: &[u8] - stat skeleton in `format`, checked once per purpose
+    pub fn audit(
+        &self,
+        root: &Path,
+        documents: &[u8],
+        stat: &[u8],
+    ) -> anyhow::Result<ComplianceReport> {
+        let mut documents = DocumentSkeleton::from_format(documents, self.format)?;
+        let purpose_types = self
+            .purpose_types
+            .iter()
+            .map(|(pattern, kind)| Regex::new(pattern).map(|pattern| (pattern, kind.as_str())))
+            .collect::<Result<Vec<(Regex, &str)>, _>>()?;
+        let no_study = StudyMetadata::default();
+        let mut report = ComplianceReport::default();
+        for product in self.discovery.list_projects(root)? {
+            for trial in &product.trials {
+                let study = self.studies.get(&trial.id).unwrap_or(&no_study);
+                documents.set_study(study);
+                let mut builder = Builder::new(&root.join(&trial.path));
+                builder
+                    .set_layout("")?
+                    .set_product_id(&product.name)
+                    .set_trial_id(&trial.name);
+                report.audited.push(trial.id.clone());
+                report.record(&trial.id, None, builder.diff_resolved(&documents)?);
+                for purpose in &trial.purpose {
+                    let kind = purpose_types
+                        .iter()
+                        .find(|(pattern, _)| pattern.is_match(&purpose.name))
+                        .map(|(_, kind)| *kind)
+                        .unwrap_or(&purpose.name);
+                    let mut skeleton = StatSkeleton::from_format(&purpose.name, stat, self.format)?;
+                    skeleton
+                        .set_root(&purpose.path.to_string_lossy())
+                        .set_purpose_type(kind)
+                        .set_study(study);
+                    report.audited.push(purpose.id.clone());
+                    report.record(
+                        &trial.id,
+                        Some(&purpose.name),
+                        builder.diff_resolved(&skeleton)?,
+                    );
+                    let area = purpose.path.join(PRODUCT);
+                    let mut programs = vec![];
+                    qc_programs(&builder.directory(), &area, &mut programs)?;
+                    for path in programs {
+                        report.issues.push(ComplianceIssue {
+                            trial: trial.id.clone(),
+                            purpose: Some(purpose.name.clone()),
+                            kind: ComplianceKind::MisplacedProgram,
+                            path,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// audit with the default `AuditOption`, trials are found by `list_projects`
+pub fn audit(root: &Path, documents: &[u8], stat: &[u8]) -> anyhow::Result<ComplianceReport> {
+    AuditOption::default().audit(root, documents, stat)
+}
+
+/// collect programs named like qc programs below dir, relative to trial directory
+fn qc_programs(trial: &Path, dir: &Path, result: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let path = trial.join(dir);
+    if !path.is_dir() {
+        return Ok(());
+    }
+    let extensions = [Engine::SAS, Engine::R, Engine::Python].map(|engine| engine.extension());
+    let mut entries = fs::read_dir(&path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let child = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            qc_programs(trial, &child, result)?;
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let program = Path::new(&name)
+            .extension()
+            .map(|ext| extensions.contains(&ext.to_string_lossy().as_ref()))
+            .unwrap_or(false);
+        if program && name.starts_with(QC_PREFIX) {
+            result.push(child);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skeleton::STAT;
+
+    #[test]
+    fn audit_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let documents = br#"[{"name": "specs"}, {"name": "protocol"},
+            {"name": "dmc", "children": [{"name": "{delivery}"}]}]"#;
+        let stat = br#"[
+            {"name": "product", "children": [{"name": "program"}]},
+            {"name": "validation", "children": [{"name": "program"}]}
+        ]"#;
+        let mut builder = Builder::new(root.path());
+        builder
+            .set_product_id("ak101")
+            .set_trial_id("202")
+            .set_delivery("dmc01");
+        builder.build(&DocumentSkeleton::new(documents)?)?;
+        builder.build(&StatSkeleton::new("CSR", stat)?)?;
+        let trial = builder.directory();
+        let csr = Path::new(STAT).join("CSR");
+        fs::remove_dir_all(trial.join("documents").join("protocol"))?;
+        fs::create_dir_all(trial.join(&csr).join("scratch"))?;
+        let program = csr.join("product").join("program");
+        fs::write(trial.join(&program).join("ae.sas"), "")?;
+        fs::write(trial.join(&program).join("v-ae.sas"), "")?;
+        fs::write(trial.join(&program).join("v-notes.txt"), "")?;
+        fs::write(
+            trial
+                .join(&csr)
+                .join("validation")
+                .join("program")
+                .join("v-ae.sas"),
+            "",
+        )?;
+        // not a product folder
+        fs::create_dir_all(root.path().join("misc").join("202"))?;
+
+        let report = audit(root.path(), documents, stat)?;
+        assert_eq!(vec!["ak101-202", "ak101-202-CSR"], report.audited);
+        assert!(!report.passed());
+        assert_eq!(
+            vec![
+                ComplianceIssue {
+                    trial: "ak101-202".into(),
+                    purpose: None,
+                    kind: ComplianceKind::MissingFolder,
+                    path: Path::new("documents").join("protocol"),
+                },
+                ComplianceIssue {
+                    trial: "ak101-202".into(),
+                    purpose: None,
+                    kind: ComplianceKind::UnresolvedPlaceholder,
+                    path: Path::new("documents").join("dmc").join("{delivery}"),
+                },
+                ComplianceIssue {
+                    trial: "ak101-202".into(),
+                    purpose: Some("CSR".into()),
+                    kind: ComplianceKind::NonStandardFolder,
+                    path: csr.join("scratch"),
+                },
+                ComplianceIssue {
+                    trial: "ak101-202".into(),
+                    purpose: Some("CSR".into()),
+                    kind: ComplianceKind::MisplacedProgram,
+                    path: program.join("v-ae.sas"),
+                },
+            ],
+            report.issues
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn audit_option_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let documents = b"- name: specs\n- name: protocol\n";
+        let stat = b"- name: product\n  children:\n    - name: program\n\
+            - name: unblinded\n  when:\n    purpose: [dmc]\n\
+            - name: pk\n  when:\n    study:\n      phase: \"1\"\n";
+        let study = StudyMetadata {
+            phase: "1".into(),
+            ..Default::default()
+        };
+        let mut builder = Builder::new(root.path());
+        builder
+            .set_layout("{product}/oncology/{trial}")?
+            .set_product_id("ABC-1234")
+            .set_trial_id("301");
+        builder.build(&DocumentSkeleton::from_format(
+            documents,
+            SkeletonFormat::Yaml,
+        )?)?;
+        let mut skeleton = StatSkeleton::from_format("DMC03", stat, SkeletonFormat::Yaml)?;
+        skeleton
+            .set_root("analysis/{purpose}")
+            .set_purpose_type("dmc")
+            .set_study(&study);
+        builder.build(&skeleton)?;
+        let program = Path::new("analysis")
+            .join("DMC03")
+            .join("product")
+            .join("program");
+        fs::write(builder.directory().join(&program).join("v-ae.sas"), "")?;
+
+        let option = AuditOption {
+            discovery: DiscoveryProfile {
+                product_pattern: r"^[A-Z]{3}-\d{4}$".into(),
+                trial_pattern: r"^\d{3}$".into(),
+                layout: "{product}/*/{trial}/analysis/{purpose}".into(),
+            },
+            format: SkeletonFormat::Yaml,
+            purpose_types: vec![(r"^DMC\d+$".into(), "dmc".into())],
+            studies: HashMap::from([("ABC-1234-oncology-301".into(), study)]),
+        };
+        let report = option.audit(root.path(), documents, stat)?;
+        assert_eq!(
+            vec!["ABC-1234-oncology-301", "ABC-1234-oncology-301-DMC03"],
+            report.audited
+        );
+        assert_eq!(
+            vec![ComplianceIssue {
+                trial: "ABC-1234-oncology-301".into(),
+                purpose: Some("DMC03".into()),
+                kind: ComplianceKind::MisplacedProgram,
+                path: program.join("v-ae.sas"),
+            }],
+            report.issues
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, Local, NaiveDate, NaiveTime,
+};
+use serde::{Deserialize, Serialize};
+
+/// provide current date to generator, use `FixedClock` to make output reproducible
+pub trait Clock {
+    fn today(&self) -> NaiveDate;
+    /// current time, midnight UTC of `today` unless clock knows the time
+    fn now(&self) -> DateTime<FixedOffset> {
+        self.today()
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+            .fixed_offset()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        Local::now().date_naive()
+    }
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().fixed_offset()
+    }
+}
+
+#[derive(Debug)]
+pub struct FixedClock(pub NaiveDate);
+
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DateFormat {
+    /// such as `05MAR2024`
+    #[default]
+    DDMONYYYY,
+    /// such as `2024-03-05`
+    ISO8601,
+    /// chrono strftime pattern, such as `%d/%m/%Y`
+    Custom(String),
+}
+
+impl DateFormat {
+    pub fn format(&self, date: NaiveDate) -> anyhow::Result<String> {
+        let pattern = match self {
+            DateFormat::DDMONYYYY => "%d%b%Y",
+            DateFormat::ISO8601 => "%Y-%m-%d",
+            DateFormat::Custom(pattern) => pattern,
+        };
+        let items = StrftimeItems::new(pattern).collect::<Vec<Item>>();
+        if items.iter().any(|item| matches!(item, Item::Error)) {
+            return Err(anyhow!("Invalid date format {}", pattern));
+        }
+        let mut result = String::new();
+        write!(result, "{}", date.format_with_items(items.into_iter()))?;
+        Ok(match self {
+            DateFormat::DDMONYYYY => result.to_uppercase(),
+            _ => result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn date_format_test() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!("05MAR2024", DateFormat::DDMONYYYY.format(date).unwrap());
+        assert_eq!("2024-03-05", DateFormat::ISO8601.format(date).unwrap());
+        assert_eq!(
+            "05/03/2024",
+            DateFormat::Custom("%d/%m/%Y".into()).format(date).unwrap()
+        );
+        assert!(DateFormat::Custom("%Q".into()).format(date).is_err());
+    }
+    #[test]
+    fn fixed_clock_test() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        assert_eq!(date, FixedClock(date).today());
+        assert_eq!(
+            "2023-03-14T00:00:00+00:00",
+            FixedClock(date).now().to_rfc3339()
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    clock::{Clock, DateFormat},
+    engine::Engine,
+    generator::{FileOutcome, StudyMetadata},
+    skeleton::{Builder, Skeleton, StatSkeleton},
+};
+
+mod header;
+
+use header::{rewrite_header, Rewrite};
+
+/// labels of program header fields rewritten by `clone_purpose`, matched ignoring case
+#[derive(Debug, Clone)]
+pub struct HeaderLabels {
+    /// whole words of old purpose are replaced with new purpose
+    pub purpose: String,
+    pub start: String,
+    pub developer: String,
+    /// a note of where the program is copied from is added after existing history
+    pub history: String,
+}
+
+impl Default for HeaderLabels {
+    fn default() -> Self {
+        HeaderLabels {
+            purpose: "Purpose".into(),
+            start: "Start Date".into(),
+            developer: "Developer".into(),
+            history: "History".into(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CloneParam {
+    /// purpose copied from, such as `CSR`
+    pub from: String,
+    /// new purpose, such as `DSUR`
+    pub to: String,
+    /// type of new purpose skeleton conditions match, such as `dsur`, same as `to` if empty
+    pub purpose_type: String,
+    /// study metadata skeleton conditions of new purpose are checked against
+    pub study: StudyMetadata,
+    /// programs relative to purpose folder, such as `product/program/ae.sas`,
+    /// every SAS, R and Python program if empty. Absolute paths and `..` fail
+    pub programs: Vec<PathBuf>,
+    /// developer of new purpose, header field is kept if empty
+    pub developer: String,
+    /// format of start date in program header
+    pub date_format: DateFormat,
+    pub labels: HeaderLabels,
+}
+
+/// a program copied into new purpose, paths are relative to purpose folders
+#[derive(Debug, Serialize)]
+pub struct ClonedProgram {
+    pub path: PathBuf,
+    /// `Skipped` if program exists in new purpose
+    pub outcome: FileOutcome,
+    /// labels of header fields rewritten
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CloneReport {
+    /// directories of new purpose skeleton created
+    pub created: Vec<PathBuf>,
+    pub programs: Vec<ClonedProgram>,
+}
+
+impl CloneReport {
+    pub fn passed(&self) -> bool {
+        !self
+            .programs
+            .iter()
+            .any(|program| matches!(program.outcome, FileOutcome::Failed(_)))
+    }
+}
+
+/// ## start a new purpose from an existing one, such as a DSUR from the CSR
+///
+/// builds the stat skeleton of new purpose, then copies programs and rewrites their headers,
+/// programs existing in new purpose are kept
+///
+/// ### Arguments
+///
+/// @ builder: &Builder - trial of both purposes
+///
+/// @ skeleton: &[u8] - stat skeleton JSON
+///
+/// @ param: &CloneParam - purposes, programs and header fields
+///
+/// @ clock: &dyn Clock - provides start date
+pub fn clone_purpose(
+    builder: &Builder,
+    skeleton: &[u8],
+    param: &CloneParam,
+    clock: &dyn Clock,
+) -> anyhow::Result<CloneReport> {
+    let from = StatSkeleton::new(&param.from, skeleton)?;
+    let mut to = StatSkeleton::new(&param.to, skeleton)?;
+    if !param.purpose_type.is_empty() {
+        to.set_purpose_type(&param.purpose_type);
+    }
+    to.set_study(&param.study);
+    let trial = builder.directory();
+    let source = trial.join(builder.resolve(&from.root())?);
+    let dest = trial.join(builder.resolve(&to.root())?);
+    if !source.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Purpose folder {} not found",
+            source.display()
+        ));
+    }
+    let programs = if param.programs.is_empty() {
+        let mut programs = vec![];
+        collect_programs(&source, Path::new(""), &mut programs)?;
+        programs
+    } else {
+        param.programs.clone()
+    };
+    let start = param.date_format.format(clock.today())?;
+    let labels = &param.labels;
+    let mut fields = vec![
+        (
+            labels.purpose.as_str(),
+            Rewrite::Word {
+                from: param.from.clone(),
+                to: param.to.clone(),
+            },
+        ),
+        (labels.start.as_str(), Rewrite::Set(start.clone())),
+        (
+            labels.history.as_str(),
+            Rewrite::Append(format!("{} copied from {}", start, param.from)),
+        ),
+    ];
+    if !param.developer.is_empty() {
+        fields.push((
+            labels.developer.as_str(),
+            Rewrite::Set(param.developer.clone()),
+        ));
+    }
+
+    let mut report = CloneReport {
+        created: builder.build(&to)?,
+        programs: vec![],
+    };
+    for path in programs {
+        let target = dest.join(&path);
+        let inside = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        let (outcome, rewritten) = if !inside {
+            let reason = format!(
+                "Program {} is not relative to purpose folder",
+                path.display()
+            );
+            (FileOutcome::Failed(reason), vec![])
+        } else if target.exists() {
+            (FileOutcome::Skipped, vec![])
+        } else {
+            copy(&source.join(&path), &target, &fields)
+                .map(|rewritten| (FileOutcome::Created, rewritten))
+                .unwrap_or_else(|e| (FileOutcome::Failed(e.to_string()), vec![]))
+        };
+        report.programs.push(ClonedProgram {
+            path,
+            outcome,
+            fields: rewritten,
+        });
+    }
+    Ok(report)
+}
+
+fn copy(source: &Path, target: &Path, fields: &[(&str, Rewrite)]) -> anyhow::Result<Vec<String>> {
+    let (content, rewritten) = rewrite_header(&fs::read(source)?, fields)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, content)?;
+    Ok(rewritten)
+}
+
+/// collect SAS, R and Python programs below dir, relative to purpose folder
+fn collect_programs(purpose: &Path, dir: &Path, result: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let extensions = [Engine::SAS, Engine::R, Engine::Python].map(|engine| engine.extension());
+    let mut entries = fs::read_dir(purpose.join(dir))?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let child = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_programs(purpose, &child, result)?;
+            continue;
+        }
+        let program = child
+            .extension()
+            .map(|ext| extensions.contains(&ext.to_string_lossy().as_ref()))
+            .unwrap_or(false);
+        if program {
+            result.push(child);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[test]
+    fn clone_purpose_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let skeleton = br#"[{"name": "product", "children": [
+            {"name": "program"}, {"name": "output"},
+            {"name": "dsur", "when": {"purpose": ["dsur"], "study": {"phase": "3"}}}
+        ]}]"#;
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak101").set_trial_id("202");
+        builder.build(&StatSkeleton::new("CSR", skeleton)?)?;
+        let stats = builder.directory().join("stats");
+        let program = Path::new("product").join("program");
+        let csr = stats.join("CSR");
+        fs::write(
+            csr.join(&program).join("ae.sas"),
+            "/* Purpose  : CSR tables of AE\n   Developer: yuki\n   Start Date: 14MAR2023\n   History  : 14MAR2023 created */\ndata ae; run;\n",
+        )?;
+        fs::write(csr.join(&program).join("dm.sas"), "/* Purpose: DM */\n")?;
+        fs::write(csr.join(&program).join("notes.txt"), "")?;
+        fs::write(csr.join("product").join("output").join("t-ae.rtf"), "")?;
+
+        let param = CloneParam {
+            from: "CSR".into(),
+            to: "DSUR".into(),
+            purpose_type: "dsur".into(),
+            study: StudyMetadata {
+                phase: "3".into(),
+                ..Default::default()
+            },
+            developer: "hiroshi".into(),
+            ..Default::default()
+        };
+        let clock = FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap());
+        let report = clone_purpose(&builder, skeleton, &param, &clock)?;
+        let dsur = stats.join("DSUR");
+        assert_eq!(
+            vec![
+                dsur.clone(),
+                dsur.join("product"),
+                dsur.join("product").join("dsur"),
+                dsur.join("product").join("output"),
+                dsur.join(&program),
+            ],
+            report.created
+        );
+        assert!(report.passed());
+        let paths = report
+            .programs
+            .iter()
+            .map(|program| program.path.clone())
+            .collect::<Vec<PathBuf>>();
+        assert_eq!(vec![program.join("ae.sas"), program.join("dm.sas")], paths);
+        assert_eq!(
+            vec!["Purpose", "Start Date", "History", "Developer"],
+            report.programs[0].fields
+        );
+        assert_eq!(
+            "/* Purpose  : DSUR tables of AE\n   Developer: hiroshi\n   Start Date: 05MAR2024\n   History  : 14MAR2023 created; 05MAR2024 copied from CSR */\ndata ae; run;\n",
+            fs::read_to_string(dsur.join(&program).join("ae.sas"))?
+        );
+        assert!(!dsur
+            .join("product")
+            .join("output")
+            .join("t-ae.rtf")
+            .exists());
+
+        let param = CloneParam {
+            programs: vec![
+                program.join("ae.sas"),
+                program.join("lb.sas"),
+                Path::new("..").join("CSR").join(&program).join("ae.sas"),
+                csr.join(&program).join("dm.sas"),
+            ],
+            ..param
+        };
+        let report = clone_purpose(&builder, skeleton, &param, &clock)?;
+        assert!(report.created.is_empty());
+        let outcomes = report
+            .programs
+            .iter()
+            .map(|program| program.outcome.name())
+            .collect::<Vec<&str>>();
+        assert_eq!(vec!["skipped", "failed", "failed", "failed"], outcomes);
+        assert!(report.programs[2]
+            .outcome
+            .reason()
+            .contains("not relative to purpose folder"));
+        assert!(!report.passed());
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use regex::bytes::{Captures, Regex};
+
+/// how the value of a header field is rewritten
+pub(crate) enum Rewrite {
+    /// replace whole value
+    Set(String),
+    /// keep value and add an entry after it, separated by `; `
+    Append(String),
+    /// replace whole words of value, such as purpose `CSR` with `DSUR`
+    Word { from: String, to: String },
+}
+
+/// ## rewrite fields of program header, such as `Developer : yuki`
+///
+/// header is the leading block of comments and blank lines, each label is rewritten once,
+/// matched ignoring case. Bytes are kept as they are otherwise, so encoding, BOM and line endings
+/// of program survive. Alignment of boxed headers is kept where the new value fits.
+/// Return program and labels rewritten
+pub(crate) fn rewrite_header(
+    content: &[u8],
+    fields: &[(&str, Rewrite)],
+) -> anyhow::Result<(Vec<u8>, Vec<String>)> {
+    let patterns = fields
+        .iter()
+        .map(|(label, _)| {
+            Regex::new(&format!(
+                r"(?i-u)^(?P<lead>[^A-Za-z\r\n]*?)(?P<label>{})(?P<sep>\s*:\s*)(?P<value>.*?)(?P<tail>\s*\*+/?\s*|\s*)$",
+                regex::escape(label)
+            ))
+        })
+        .collect::<Result<Vec<Regex>, _>>()?;
+    let mut done = vec![false; fields.len()];
+    let mut result = Vec::with_capacity(content.len());
+    let mut in_comment = false;
+    let mut in_header = true;
+    for (index, line) in content.split_inclusive(|&b| b == b'\n').enumerate() {
+        let (text, ending) = split_ending(line);
+        let mut trimmed = if index == 0 {
+            text.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(text)
+        } else {
+            text
+        };
+        while let Some((first, rest)) = trimmed.split_first() {
+            if !first.is_ascii_whitespace() {
+                break;
+            }
+            trimmed = rest;
+        }
+        in_header = in_header
+            && (in_comment
+                || trimmed.is_empty()
+                || [b"/*".as_slice(), b"*", b"#"]
+                    .iter()
+                    .any(|open| trimmed.starts_with(open)));
+        if !in_header {
+            result.extend_from_slice(line);
+            continue;
+        }
+        in_comment = comment_state(text, in_comment);
+        let found = patterns
+            .iter()
+            .enumerate()
+            .find_map(|(i, pattern)| match done[i] {
+                true => None,
+                false => pattern.captures(text).map(|caps| (i, caps)),
+            });
+        match found {
+            Some((i, caps)) => {
+                done[i] = true;
+                result.extend(replace(&caps, &fields[i].1)?);
+                result.extend_from_slice(ending);
+            }
+            None => result.extend_from_slice(line),
+        }
+    }
+    let rewritten = fields
+        .iter()
+        .zip(done)
+        .filter(|(_, done)| *done)
+        .map(|((label, _), _)| label.to_string())
+        .collect();
+    Ok((result, rewritten))
+}
+
+fn split_ending(line: &[u8]) -> (&[u8], &[u8]) {
+    let text = line.strip_suffix(b"\n").unwrap_or(line);
+    let text = text.strip_suffix(b"\r").unwrap_or(text);
+    line.split_at(text.len())
+}
+
+/// whether line ends inside a `/* */` comment
+fn comment_state(text: &[u8], in_comment: bool) -> bool {
+    let last = |pattern: &[u8]| text.windows(2).rposition(|window| window == pattern);
+    match (last(b"/*"), last(b"*/")) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => in_comment,
+    }
+}
+
+fn replace(caps: &Captures, rewrite: &Rewrite) -> anyhow::Result<Vec<u8>> {
+    let value = &caps["value"];
+    let new = match rewrite {
+        Rewrite::Set(text) => text.as_bytes().to_vec(),
+        Rewrite::Append(text) if value.is_empty() => text.as_bytes().to_vec(),
+        Rewrite::Append(text) => [value, b"; ", text.as_bytes()].concat(),
+        Rewrite::Word { from, to } => Regex::new(&format!(r"\b{}\b", regex::escape(from)))?
+            .replace_all(value, to.as_bytes())
+            .to_vec(),
+    };
+    let mut tail = &caps["tail"];
+    let mut line = [&caps["lead"], &caps["label"], &caps["sep"], new.as_slice()].concat();
+    if new.len() < value.len() {
+        line.resize(line.len() + value.len() - new.len(), b' ');
+    } else if !tail.trim_ascii().is_empty() {
+        let mut extra = new.len() - value.len();
+        while extra > 0 && tail.starts_with(b"  ") {
+            tail = &tail[1..];
+            extra -= 1;
+        }
+    }
+    line.extend_from_slice(tail);
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_header_test() -> anyhow::Result<()> {
+        let program = "\u{feff}/*************************************\r\n\
+            * Program   : ae.sas                  *\r\n\
+            * Purpose   : CSR tables of AE        *\r\n\
+            * Developer : yuki                    *\r\n\
+            * Start Date: 14MAR2023               *\r\n\
+            *************************************/\r\n\
+            %let purpose=CSR;\r\n\
+            * Developer : kept;\r\n";
+        let fields = [
+            (
+                "purpose",
+                Rewrite::Word {
+                    from: "CSR".into(),
+                    to: "DSUR".into(),
+                },
+            ),
+            ("Developer", Rewrite::Set("hiroshi".into())),
+            ("Start Date", Rewrite::Set("05MAR2024".into())),
+            ("History", Rewrite::Append("copied".into())),
+        ];
+        let (content, rewritten) = rewrite_header(program.as_bytes(), &fields)?;
+        assert_eq!(
+            "\u{feff}/*************************************\r\n\
+            * Program   : ae.sas                  *\r\n\
+            * Purpose   : DSUR tables of AE       *\r\n\
+            * Developer : hiroshi                 *\r\n\
+            * Start Date: 05MAR2024               *\r\n\
+            *************************************/\r\n\
+            %let purpose=CSR;\r\n\
+            * Developer : kept;\r\n",
+            String::from_utf8(content)?
+        );
+        assert_eq!(vec!["purpose", "Developer", "Start Date"], rewritten);
+
+        let (content, _) = rewrite_header(
+            b"# Developer: yuki\nlibrary(haven)\n",
+            &[("Developer", Rewrite::Set("hiroshi".into()))],
+        )?;
+        assert_eq!(
+            b"# Developer: hiroshi\nlibrary(haven)\n".as_slice(),
+            content
+        );
+
+        let (content, _) = rewrite_header(
+            b"* History : created  *\n",
+            &[("History", Rewrite::Append("copied".into()))],
+        )?;
+        assert_eq!(b"* History : created; copied *\n".as_slice(), content);
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// Language the generated programs are written in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Engine {
+    #[default]
+    SAS,
+    R,
+    Python,
+}
+
+/// Comment delimiters of an engine, `close` is empty for line comments
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Comment {
+    pub open: String,
+    pub close: String,
+}
+
+impl Engine {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Engine::SAS => "SAS",
+            Engine::R => "R",
+            Engine::Python => "Python",
+        }
+    }
+
+    /// file extension of programs and templates, without leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Engine::SAS => "sas",
+            Engine::R => "R",
+            Engine::Python => "py",
+        }
+    }
+
+    /// file extension of datasets the engine produces, such as production datasets QC compares against
+    pub fn dataset_extension(&self) -> &'static str {
+        match self {
+            Engine::SAS => "sas7bdat",
+            Engine::R => "rds",
+            Engine::Python => "parquet",
+        }
+    }
+
+    pub fn comment(&self) -> Comment {
+        let (open, close) = match self {
+            Engine::SAS => ("/*", "*/"),
+            Engine::R | Engine::Python => ("#", ""),
+        };
+        Comment {
+            open: open.into(),
+            close: close.into(),
+        }
+    }
+
+    /// sub directory holding templates of this engine, SAS templates stay in the root
+    /// to keep existing template folders working
+    pub fn template_directory(&self) -> Option<&'static str> {
+        match self {
+            Engine::SAS => None,
+            Engine::R => Some("r"),
+            Engine::Python => Some("python"),
+        }
+    }
+
+    /// statement running another program, used by batch drivers
+    pub fn include(&self, path: &str) -> String {
+        match self {
+            Engine::SAS => format!("%include \"{}\";", path),
+            Engine::R => format!("source(\"{}\")", path.replace('\\', "/")),
+            Engine::Python => format!("exec(open(r\"{}\", encoding=\"utf-8\").read())", path),
+        }
+    }
+
+    /// whether a UTF-8 BOM is written by default
+    pub fn bom(&self) -> bool {
+        match self {
+            Engine::SAS => true,
+            Engine::R | Engine::Python => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn engine_test() {
+        assert_eq!(Engine::default(), Engine::SAS);
+        assert_eq!(Engine::R.extension(), "R");
+        assert_eq!(Engine::Python.comment().open, "#");
+        assert!(Engine::SAS.bom());
+        assert!(!Engine::R.bom());
+        assert_eq!(Engine::R.template_directory(), Some("r"));
+        assert_eq!(Engine::SAS.dataset_extension(), "sas7bdat");
+        assert_eq!(Engine::SAS.include("dm.sas"), r#"%include "dm.sas";"#);
+        assert_eq!(Engine::R.include(r"prg\adsl.R"), r#"source("prg/adsl.R")"#);
+    }
+}
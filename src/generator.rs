@@ -1,10 +1,23 @@
-use crate::reader::{item::ConfigItem, new_reader, Kind};
-use crate::render::{Item, Render};
+use crate::clock::{Clock, DateFormat, SystemClock};
+use crate::engine::Engine;
+use crate::reader::{item::ConfigItem, new_reader, Kind, Output, OutputKind, OutputRules};
+use crate::render::{Compare, Item, OutputProfile, Render, Scope};
+use crate::template::{Version, VersionManager};
 use anyhow::Ok;
-use chrono::Local;
+pub use report::{FileOutcome, RunReport, SpecFile};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use staging::Staging;
 use std::collections::HashMap;
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tera::Context;
+
+mod driver;
+mod report;
+mod staging;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Group {
@@ -15,11 +28,67 @@ pub enum Group {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Param {
     pub study: String,
-    pub engine: String,
+    pub engine: Engine,
     pub group: Group,
     pub custom_code: Vec<String>,
     pub path: String,
     pub template: String,
+    /// format of start date in program header
+    #[serde(default)]
+    pub date_format: DateFormat,
+    /// production datasets compared against in qc programs
+    #[serde(default)]
+    pub compare: CompareParam,
+    #[serde(default)]
+    pub metadata: StudyMetadata,
+    /// version of template, exposed to templates as `version`
+    #[serde(default)]
+    pub version: Option<Version>,
+    /// user defined variables, exposed to templates as `vars`
+    #[serde(default)]
+    pub variables: HashMap<String, Value>,
+    /// encoding, BOM and line ending of programs, engine default if none
+    #[serde(default)]
+    pub profile: Option<OutputProfile>,
+}
+
+impl Param {
+    pub fn output_profile(&self) -> OutputProfile {
+        self.profile
+            .clone()
+            .unwrap_or_else(|| OutputProfile::for_engine(&self.engine))
+    }
+}
+
+/// study level metadata, exposed to templates as `study`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StudyMetadata {
+    pub protocol_title: String,
+    pub phase: String,
+    pub sponsor: String,
+    /// versions of standards, such as `SDTMIG` to `3.3`
+    pub standards: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompareParam {
+    /// library of production datasets, `sdtm` or `adam` by kind if empty
+    pub library: String,
+    /// folder of production datasets
+    pub path: String,
+    /// options of `proc compare` statement
+    pub options: Vec<String>,
+}
+
+impl Default for CompareParam {
+    fn default() -> Self {
+        CompareParam {
+            library: "".into(),
+            path: "".into(),
+            options: vec!["listall".into()],
+        }
+    }
 }
 
 pub struct Generator {
@@ -27,12 +96,38 @@ pub struct Generator {
     template: Render,
     kind: Kind,
     assignment: Option<HashMap<String, String>>,
+    output_rules: OutputRules,
+    clock: Box<dyn Clock>,
+    /// spec or TOP items are read from, recorded in run reports
+    spec: Option<PathBuf>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// serialized with `existed` besides `outcome`, kept for consumers of earlier reports
+#[derive(Debug, Deserialize)]
 pub struct FileResult {
-    name: String,
-    existed: bool,
+    pub name: String,
+    pub outcome: FileOutcome,
+}
+
+impl Serialize for FileResult {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("FileResult", 3)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("existed", &self.existed())?;
+        state.serialize_field("outcome", &self.outcome)?;
+        state.end()
+    }
+}
+
+impl FileResult {
+    /// whether file existed before generation
+    pub fn existed(&self) -> bool {
+        matches!(
+            self.outcome,
+            FileOutcome::Skipped | FileOutcome::Overwritten
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,6 +144,16 @@ impl Generator {
         force: bool,
     ) -> anyhow::Result<Generator> {
         let items = new_reader(&kind, config).read(force)?;
+        let mut generator = Generator::from_items(items, kind, assignment)?;
+        generator.spec = Some(config.into());
+        Ok(generator)
+    }
+    /// create generator from config items already read, such as items picked by user
+    pub fn from_items(
+        items: Vec<ConfigItem>,
+        kind: Kind,
+        assignment: Vec<Assignment>,
+    ) -> anyhow::Result<Generator> {
         let template = Render::new()?;
         let assignment = if assignment.len() > 0 {
             let mut assign_map = HashMap::new();
@@ -64,9 +169,75 @@ impl Generator {
             template,
             kind,
             assignment,
+            output_rules: OutputRules::default(),
+            clock: Box::new(SystemClock),
+            spec: None,
         })
     }
-    pub fn render(&self, mut dest: &Path, param: &Param) -> anyhow::Result<Vec<FileResult>> {
+    /// set rules to classify TFL outputs whose type is not declared in TOP
+    pub fn set_output_rules(&mut self, rules: OutputRules) -> &mut Self {
+        self.output_rules = rules;
+        self
+    }
+    /// render with template set of manager, so templates can extend, include and import shared templates
+    pub fn set_templates(&mut self, manager: &VersionManager) -> anyhow::Result<&mut Self> {
+        self.template = Render::with_templates(manager.template_set()?);
+        Ok(self)
+    }
+    /// set clock providing start date, use `FixedClock` for regression tests and re-generation
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Box::new(clock);
+        self
+    }
+    /// render programs of every item, existing programs are kept and nothing is written unless the whole batch renders
+    pub fn render(&self, dest: &Path, param: &Param) -> anyhow::Result<Vec<FileResult>> {
+        let (staging, result) = self.stage(dest, param)?;
+        let failed = result
+            .iter()
+            .filter_map(|file| match &file.outcome {
+                FileOutcome::Failed(reason) => Some(format!("{}: {}", file.name, reason)),
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!(failed.join("\n")));
+        }
+        staging.commit()?;
+        Ok(result)
+    }
+
+    /// ## render programs of every item and report outcome of each program
+    ///
+    /// same as `render`, but failures are recorded in report instead of returned as error,
+    /// programs not written because batch is rolled back are reported failed as well
+    pub fn run(&self, dest: &Path, param: &Param) -> anyhow::Result<RunReport> {
+        let (staging, mut files) = self.stage(dest, param)?;
+        let rollback = if files
+            .iter()
+            .any(|file| matches!(file.outcome, FileOutcome::Failed(_)))
+        {
+            Some("Not written, batch is rolled back".to_string())
+        } else {
+            staging.commit().err().map(|e| format!("{:#}", e))
+        };
+        if let Some(reason) = rollback {
+            for file in files.iter_mut() {
+                if FileOutcome::Created.eq(&file.outcome) {
+                    file.outcome = FileOutcome::Failed(reason.clone());
+                }
+            }
+        }
+        RunReport::new(
+            &self.kind,
+            self.spec.as_deref(),
+            param,
+            files,
+            self.clock.as_ref(),
+        )
+    }
+
+    /// render programs into staging area of dest, every item is rendered even if some fail
+    fn stage(&self, mut dest: &Path, param: &Param) -> anyhow::Result<(Staging, Vec<FileResult>)> {
         let mut result = vec![];
         if dest.is_file() {
             dest = dest.parent().unwrap();
@@ -74,63 +245,196 @@ impl Generator {
         if !dest.exists() {
             fs::create_dir_all(dest)?;
         }
-        let current = Local::now().format("%e%b%Y").to_string().to_uppercase();
-        for ConfigItem {
-            name,
-            supp,
-            qc_required,
-        } in &self.items
-        {
-            if (!qc_required) && Group::Qc.eq(&param.group) {
+        let current = param.date_format.format(self.clock.today())?;
+        let profile = param.output_profile();
+        let mut staging = Staging::new(dest)?;
+        let template = self.template.prepare(&param.template);
+        for meta in &self.items {
+            if (!meta.qc_required) && Group::Qc.eq(&param.group) {
                 continue;
             }
-            let developer = if let Some(assignments) = &self.assignment {
-                let task = if Group::Qc.eq(&param.group) {
-                    format!("{}|qc", name)
-                } else {
-                    format!("{}|dev", name)
-                };
-                if let Some(developer) = assignments.get(&task) {
-                    format!("{:27}", developer.to_string())
-                } else {
-                    format!("{:27}", " ")
-                }
+            let filename = filename(&meta.name, &param.group, &param.engine);
+            let outcome = if dest.join(&filename).exists() {
+                FileOutcome::Skipped
             } else {
-                format!("{:27}", " ")
+                let staged = staging.path(&filename);
+                let template = template.as_ref().map_err(|e| anyhow::anyhow!("{:#}", e));
+                self.context(meta, param, &current)
+                    .and_then(|ctx| template?.render_context(&ctx, &staged, &profile))
+                    .map(|_| FileOutcome::Created)
+                    .unwrap_or_else(|e| FileOutcome::Failed(format!("{:#}", e)))
             };
-            let item = Item {
-                name: name.into(),
-                study: param.study.clone(),
-                engine: param.engine.clone(),
-                purpose: purpose(&name, &param.group, &self.kind)?,
-                start: current.clone(),
-                description: "Create".into(),
-                supp: *supp,
-                developer,
-                slot: param.custom_code.clone(),
-                path: param.path.clone(),
-            };
-            let filename = filename(name, &param.group);
-            let existed = self
-                .template
-                .render(&param.template, &item, &dest.join(&filename))?;
             result.push(FileResult {
                 name: filename,
-                existed,
+                outcome,
             })
         }
-        Ok(result)
+        Ok((staging, result))
     }
+
+    /// ## render one item into a string without writing to disk
+    ///
+    /// content is neither encoded nor prefixed with BOM, for previewing a template while editing it
+    pub fn preview(&self, meta: &ConfigItem, param: &Param) -> anyhow::Result<String> {
+        let current = param.date_format.format(self.clock.today())?;
+        let ctx = self.context(meta, param, &current)?;
+        self.template.render_str(&param.template, &ctx)
+    }
+
+    fn context(&self, meta: &ConfigItem, param: &Param, current: &str) -> anyhow::Result<Context> {
+        let ConfigItem {
+            name,
+            supp,
+            output,
+            keys,
+            ..
+        } = meta;
+        let developer = if let Some(assignments) = &self.assignment {
+            let task = if Group::Qc.eq(&param.group) {
+                format!("{}|qc", name)
+            } else {
+                format!("{}|dev", name)
+            };
+            assignments.get(&task).cloned().unwrap_or_default()
+        } else {
+            "".into()
+        };
+        let output = match self.kind {
+            Kind::TFL => self.output_rules.classify(name, *output),
+            _ => None,
+        };
+        let item = Item {
+            name: name.into(),
+            study: param.study.clone(),
+            engine: param.engine.name().into(),
+            comment: param.engine.comment(),
+            purpose: purpose(name, &param.group, &self.kind, output.as_ref()),
+            start: current.into(),
+            description: "Create".into(),
+            supp: *supp,
+            developer,
+            slot: param.custom_code.clone(),
+            path: param.path.clone(),
+            output,
+            compare: match param.group {
+                Group::Qc => compare(name, keys, &self.kind, &param.engine, &param.compare),
+                Group::Dev => None,
+            },
+        };
+        Ok(Context::from_serialize(Scope {
+            item: &item,
+            meta,
+            study: &param.metadata,
+            group: &param.group,
+            version: param.version.as_ref(),
+            vars: &param.variables,
+        })?)
+    }
+}
+
+/// ## context of a made up item, used to lint a template of kind
+///
+/// group follows role of version, `qc` or else dev, so `item.compare` is only filled for qc
+/// and `item.output` only for TFL, as they are when rendering
+pub(crate) fn sample_context(
+    kind: &Kind,
+    engine: &Engine,
+    version: &Version,
+) -> anyhow::Result<Context> {
+    let group = if version.role.eq_ignore_ascii_case("qc") {
+        Group::Qc
+    } else {
+        Group::Dev
+    };
+    let name = match kind {
+        Kind::SDTM => "ae",
+        Kind::ADAM => "adae",
+        Kind::TFL => "t-14-01-01-dm",
+    };
+    let meta = ConfigItem {
+        name: name.into(),
+        supp: true,
+        qc_required: true,
+        output: None,
+        depends: vec!["adsl".into()],
+        keys: vec!["USUBJID".into()],
+    };
+    let output = match kind {
+        Kind::TFL => OutputRules::default().classify(name, Some(OutputKind::Table)),
+        _ => None,
+    };
+    let item = Item {
+        name: name.into(),
+        study: "STUDY".into(),
+        engine: engine.name().into(),
+        comment: engine.comment(),
+        purpose: purpose(name, &group, kind, output.as_ref()),
+        start: "01JAN2024".into(),
+        description: "Create".into(),
+        supp: true,
+        developer: "developer".into(),
+        slot: vec![],
+        path: "".into(),
+        output,
+        compare: match group {
+            Group::Qc => compare(name, &meta.keys, kind, engine, &CompareParam::default()),
+            Group::Dev => None,
+        },
+    };
+    Ok(Context::from_serialize(Scope {
+        item: &item,
+        meta: &meta,
+        study: &StudyMetadata::default(),
+        group: &group,
+        version: Some(version),
+        vars: &HashMap::new(),
+    })?)
 }
 
-fn filename(item: &str, group: &Group) -> String {
+fn filename(item: &str, group: &Group, engine: &Engine) -> String {
     match group {
-        Group::Dev => format!("{}.sas", item),
-        Group::Qc => format!("v-{}.sas", item),
+        Group::Dev => format!("{}.{}", item, engine.extension()),
+        Group::Qc => format!("v-{}.{}", item, engine.extension()),
     }
 }
 
-fn purpose(item: &str, group: &Group, kind: &Kind) -> anyhow::Result<String> {
+/// production dataset a qc program compares against, none for TFL outputs which are not datasets
+fn compare(
+    item: &str,
+    keys: &[String],
+    kind: &Kind,
+    engine: &Engine,
+    param: &CompareParam,
+) -> Option<Compare> {
+    let default_library = match kind {
+        Kind::SDTM => "sdtm",
+        Kind::ADAM => "adam",
+        Kind::TFL => return None,
+    };
+    let library = if param.library.is_empty() {
+        default_library.into()
+    } else {
+        param.library.clone()
+    };
+    let path = if param.path.is_empty() {
+        "".into()
+    } else {
+        Path::new(&param.path)
+            .join(format!("{}.{}", item, engine.dataset_extension()))
+            .to_string_lossy()
+            .to_string()
+    };
+    Some(Compare {
+        base: format!("{}.{}", library, item),
+        library,
+        dataset: item.into(),
+        path,
+        keys: keys.to_vec(),
+        options: param.options.clone(),
+    })
+}
+
+fn purpose(item: &str, group: &Group, kind: &Kind, output: Option<&Output>) -> String {
     let action = match group {
         Group::Dev => "To Create",
         Group::Qc => "To Qc",
@@ -138,24 +442,324 @@ fn purpose(item: &str, group: &Group, kind: &Kind) -> anyhow::Result<String> {
     let output = match kind {
         Kind::SDTM => format!("SDTM.{} dataset", item.to_uppercase()),
         Kind::ADAM => format!("ADAM.{} dataset", item.to_uppercase()),
-        Kind::TFL => {
-            let output_type = if item.starts_with("t") {
-                "table"
-            } else if item.starts_with("f") {
-                "figure"
-            } else {
-                "listing"
-            };
-            let name = String::from_utf8(item.as_bytes().get(2..).unwrap().to_vec())?;
-            format!("{} {}", output_type, name.replace("-", "."))
-        }
+        Kind::TFL => match output {
+            Some(Output { kind, title, .. }) => {
+                format!("{} {}", kind.name(), title.replace('-', "."))
+            }
+            None => format!("output {}", item),
+        },
     };
-    Ok(format!("{} {}", action, output))
+    format!("{} {}", action, output)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::FixedClock;
+    use chrono::NaiveDate;
+
+    /// `Param` of study `AK112-303` with defaults, tests override fields they check
+    pub(super) fn param(engine: Engine, group: Group, template: &str) -> Param {
+        Param {
+            study: "AK112-303".into(),
+            engine,
+            group,
+            custom_code: vec![],
+            template: template.into(),
+            path: "".into(),
+            date_format: DateFormat::DDMONYYYY,
+            compare: CompareParam::default(),
+            metadata: StudyMetadata::default(),
+            version: None,
+            variables: HashMap::new(),
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn fixed_clock_test() -> anyhow::Result<()> {
+        let items = vec![ConfigItem {
+            name: "ae".into(),
+            supp: true,
+            qc_required: true,
+            output: None,
+            depends: vec![],
+            keys: vec![],
+        }];
+        let mut g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        g.set_clock(FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()));
+        let dest = tempfile::tempdir()?;
+        let mut param = param(Engine::R, Group::Dev, "{{ item.start }}");
+        g.render(dest.path(), &param)?;
+        assert_eq!("05MAR2024", fs::read_to_string(dest.path().join("ae.R"))?);
+        param.group = Group::Qc;
+        param.date_format = DateFormat::ISO8601;
+        g.render(dest.path(), &param)?;
+        assert_eq!(
+            "2024-03-05",
+            fs::read_to_string(dest.path().join("v-ae.R"))?
+        );
+        Ok(())
+    }
+    #[test]
+    fn compare_test() -> anyhow::Result<()> {
+        let items = vec![ConfigItem {
+            name: "ae".into(),
+            supp: true,
+            qc_required: true,
+            output: None,
+            depends: vec![],
+            keys: vec!["USUBJID".into(), "AESEQ".into()],
+        }];
+        let g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        let dest = tempfile::tempdir()?;
+        let template = "{% if item.compare %}proc compare base={{ item.compare.base }} \
+            compare=qc_{{ item.compare.dataset }} {{ item.compare.options | join(sep=\" \") }};\n\
+            id {{ item.compare.keys | join(sep=\" \") }};\nrun;{% endif %}";
+        let mut param = param(Engine::SAS, Group::Dev, template);
+        g.render(dest.path(), &param)?;
+        assert_eq!("\u{feff}", fs::read_to_string(dest.path().join("ae.sas"))?);
+        param.group = Group::Qc;
+        param.compare.options.push("criterion=1e-8".into());
+        g.render(dest.path(), &param)?;
+        assert_eq!(
+            "\u{feff}proc compare base=sdtm.ae compare=qc_ae listall criterion=1e-8;\nid USUBJID AESEQ;\nrun;",
+            fs::read_to_string(dest.path().join("v-ae.sas"))?
+        );
+
+        let partial: Param = serde_json::from_value(serde_json::json!({
+            "study": "AK112-303",
+            "engine": "R",
+            "group": "Qc",
+            "custom_code": [],
+            "path": "",
+            "template": "{{ item.compare.path }}",
+            "compare": {"library": "raw", "path": "prod"}
+        }))?;
+        assert_eq!(vec!["listall"], partial.compare.options);
+        g.render(dest.path(), &partial)?;
+        assert_eq!(
+            Path::new("prod").join("ae.rds").to_string_lossy(),
+            fs::read_to_string(dest.path().join("v-ae.R"))?
+        );
+
+        let tfl = Generator::from_items(
+            vec![ConfigItem {
+                name: "t-14-1-1-dm".into(),
+                supp: false,
+                qc_required: true,
+                output: None,
+                depends: vec![],
+                keys: vec![],
+            }],
+            Kind::TFL,
+            vec![],
+        )?;
+        param.template = "{% if item.compare %}compare{% endif %}".into();
+        tfl.render(dest.path(), &param)?;
+        assert_eq!(
+            "\u{feff}",
+            fs::read_to_string(dest.path().join("v-t-14-1-1-dm.sas"))?
+        );
+        Ok(())
+    }
+    #[test]
+    fn scope_test() -> anyhow::Result<()> {
+        let items = vec![ConfigItem {
+            name: "adsl".into(),
+            supp: false,
+            qc_required: true,
+            output: None,
+            depends: vec!["dm".into()],
+            keys: vec![],
+        }];
+        let g = Generator::from_items(items, Kind::ADAM, vec![])?;
+        let dest = tempfile::tempdir()?;
+        let mut metadata = StudyMetadata {
+            sponsor: "Akeso".into(),
+            ..Default::default()
+        };
+        metadata.standards.insert("ADaMIG".into(), "1.3".into());
+        let mut variables = HashMap::new();
+        variables.insert("reviewer".into(), Value::from("yuki"));
+        variables.insert("tags".into(), serde_json::json!(["eff", "safety"]));
+        let param = Param {
+            metadata,
+            version: Some(Version {
+                name: "v1".into(),
+                role: "dev".into(),
+                offical: true,
+            }),
+            variables,
+            ..param(
+                Engine::R,
+                Group::Dev,
+                "{{ meta.depends.0 }} {{ study.sponsor }} {{ study.standards.ADaMIG }} \
+                {{ group }} {{ version.name }} {{ vars.reviewer }} {{ vars.tags | length }}",
+            )
+        };
+        g.render(dest.path(), &param)?;
+        assert_eq!(
+            "dm Akeso 1.3 Dev v1 yuki 2",
+            fs::read_to_string(dest.path().join("adsl.R"))?
+        );
+        Ok(())
+    }
+    #[test]
+    fn template_set_test() -> anyhow::Result<()> {
+        let offical = tempfile::tempdir()?;
+        let private = tempfile::tempdir()?;
+        let shared = offical.path().join("shared");
+        fs::create_dir_all(&shared)?;
+        fs::write(
+            shared.join("base.sas"),
+            "/* {{ item.name }} */\n{% block body %}{% endblock body %}\n{% include \"footer.sas\" %}",
+        )?;
+        fs::write(shared.join("footer.sas"), "%checklog;")?;
+        fs::write(
+            shared.join("macros.sas"),
+            "{% macro sort(ds) %}proc sort data={{ ds }}; run;{% endmacro sort %}",
+        )?;
+        let manager = VersionManager::new(&crate::VersionManagerParam {
+            offical_path: offical.path(),
+            private_path: private.path(),
+            kind: Kind::SDTM,
+            engine: Engine::SAS,
+        });
+        let items = vec![ConfigItem {
+            name: "ae".into(),
+            supp: false,
+            qc_required: true,
+            output: None,
+            depends: vec![],
+            keys: vec![],
+        }];
+        let mut g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        g.set_templates(&manager)?;
+        let dest = tempfile::tempdir()?;
+        let param = param(
+            Engine::SAS,
+            Group::Dev,
+            "{% extends \"base.sas\" %}{% import \"macros.sas\" as m %}\
+                {% block body %}{{ m::sort(ds=item.name) }}{% endblock body %}",
+        );
+        g.render(dest.path(), &param)?;
+        assert_eq!(
+            "\u{feff}/* ae */\nproc sort data=ae; run;\n%checklog;",
+            fs::read_to_string(dest.path().join("ae.sas"))?
+        );
+        Ok(())
+    }
+    #[test]
+    fn profile_test() -> anyhow::Result<()> {
+        use crate::render::{Encoding, LineEnding};
+        let items = vec![ConfigItem {
+            name: "dm".into(),
+            supp: false,
+            qc_required: true,
+            output: None,
+            depends: vec![],
+            keys: vec![],
+        }];
+        let g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        let dest = tempfile::tempdir()?;
+        let mut param = Param {
+            profile: Some(OutputProfile {
+                encoding: Encoding::LATIN1,
+                bom: true,
+                line_ending: LineEnding::CRLF,
+            }),
+            ..param(
+                Engine::SAS,
+                Group::Dev,
+                "/* {{ vars.author }} */\n%let x=1;\n",
+            )
+        };
+        param.variables.insert("author".into(), Value::from("José"));
+        g.render(dest.path(), &param)?;
+        assert_eq!(
+            b"/* Jos\xe9 */\r\n%let x=1;\r\n".to_vec(),
+            fs::read(dest.path().join("dm.sas"))?
+        );
+        param.group = Group::Qc;
+        param.variables.insert("author".into(), Value::from("陈"));
+        let error = g.render(dest.path(), &param).unwrap_err();
+        assert!(error.to_string().contains("line 1 column 4"));
+        assert!(!dest.path().join("v-dm.sas").exists());
+        Ok(())
+    }
+    #[test]
+    fn preview_test() -> anyhow::Result<()> {
+        let meta = ConfigItem {
+            name: "t-14-01-01-dm".into(),
+            supp: false,
+            qc_required: true,
+            output: None,
+            depends: vec![],
+            keys: vec![],
+        };
+        let mut g = Generator::from_items(vec![], Kind::TFL, vec![])?;
+        g.set_clock(FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()));
+        let param = param(
+            Engine::SAS,
+            Group::Qc,
+            "{{ item.name }} {{ item.start }} {{ item.output.number }}\n",
+        );
+        assert_eq!(
+            "t-14-01-01-dm 05MAR2024 14.01.01\n",
+            g.preview(&meta, &param)?
+        );
+        Ok(())
+    }
+    #[test]
+    fn batch_rollback_test() -> anyhow::Result<()> {
+        let items = ["ae", "suppae", "cm"]
+            .iter()
+            .map(|name| ConfigItem {
+                name: name.to_string(),
+                supp: false,
+                qc_required: true,
+                output: None,
+                depends: vec![],
+                keys: vec![],
+            })
+            .collect();
+        let g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        let dest = tempfile::tempdir()?;
+        let mut param = param(Engine::SAS, Group::Dev, "{{ item.name | sas_name(max=2) }}");
+        assert!(g.render(dest.path(), &param).is_err());
+        assert_eq!(0, fs::read_dir(dest.path())?.count());
+
+        param.template = "{{ item.name }}".into();
+        let result = g.render(dest.path(), &param)?;
+        assert_eq!(3, result.len());
+        assert_eq!(3, fs::read_dir(dest.path())?.count());
+        Ok(())
+    }
+    #[test]
+    fn purpose_test() {
+        let rules = OutputRules::default();
+        let name = "l-16-02-07-06-irae-ss";
+        let output = rules.classify(name, None);
+        assert_eq!(
+            "To Qc listing 16.02.07.06.irae.ss",
+            purpose(name, &Group::Qc, &Kind::TFL, output.as_ref())
+        );
+        assert_eq!(
+            "To Create output tte-km",
+            purpose("tte-km", &Group::Dev, &Kind::TFL, None)
+        );
+        assert_eq!(
+            "To Create SDTM.AE dataset",
+            purpose("ae", &Group::Dev, &Kind::SDTM, None)
+        );
+    }
+    #[test]
+    fn filename_test() {
+        assert_eq!("ae.sas", filename("ae", &Group::Dev, &Engine::SAS));
+        assert_eq!("v-adsl.R", filename("adsl", &Group::Qc, &Engine::R));
+        assert_eq!("v-adsl.py", filename("adsl", &Group::Qc, &Engine::Python));
+    }
     #[test]
     fn sdtm_generate_test() {
         let dev_template = fs::read_to_string(Path::new(
@@ -167,20 +771,12 @@ mod tests {
         ))
         .unwrap();
         let dev = Param {
-            study: "AK112-303".into(),
-            engine: "SAS EG".into(),
-            group: Group::Dev,
             custom_code: vec!["%format".into(), "%checklog".into(), "".into()],
-            template: dev_template,
-            path: "".into(),
+            ..param(Engine::SAS, Group::Dev, &dev_template)
         };
         let qc = Param {
-            study: "AK112-303".into(),
-            engine: "SAS EG".into(),
-            group: Group::Qc,
             custom_code: vec!["%format".into(), "%checklog".into(), "".into()],
-            template: qc_template,
-            path: "".into(),
+            ..param(Engine::SAS, Group::Qc, &qc_template)
         };
         let config = Path::new(
             r"D:\Studies\ak112\303\documents\specs\AK112-303 SDTM Specification v0.2.xlsx",
@@ -194,20 +790,12 @@ mod tests {
     #[test]
     fn adam_generate_test() {
         let dev = Param {
-            study: "AK112-303".into(),
-            engine: "SAS EG".into(),
-            group: Group::Dev,
             custom_code: vec!["%format".into(), "%checklog".into()],
-            template: "".into(),
-            path: "".into(),
+            ..param(Engine::SAS, Group::Dev, "")
         };
         let qc = Param {
-            study: "AK112-303".into(),
-            engine: "SAS EG".into(),
-            group: Group::Qc,
             custom_code: vec!["%format".into(), "%checklog".into()],
-            template: "".into(),
-            path: "".into(),
+            ..param(Engine::SAS, Group::Qc, "")
         };
         let config = Path::new(
             r"D:\projects\rusty\mobius_kit\.mocks\specs\AK112-303 ADaM Specification v0.2.xlsx",
@@ -221,20 +809,12 @@ mod tests {
     #[test]
     fn tfl_generate_test() {
         let dev = Param {
-            study: "AK112-303".into(),
-            engine: "SAS EG".into(),
-            group: Group::Dev,
             custom_code: vec!["".into()],
-            template: "".into(),
-            path: "".into(),
+            ..param(Engine::SAS, Group::Dev, "")
         };
         let qc = Param {
-            study: "AK112-303".into(),
-            engine: "SAS EG".into(),
-            group: Group::Qc,
             custom_code: vec!["".into()],
-            template: "".into(),
-            path: "".into(),
+            ..param(Engine::SAS, Group::Qc, "")
         };
         let config = Path::new(r"D:\Studies\ak112\303\stats\CSR\utility\top-ak112-303-CSR.xlsx");
         let dev_dest = Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\generated\tfl\dev");
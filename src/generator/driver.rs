@@ -0,0 +1,213 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use super::{filename, FileOutcome, FileResult, Generator, Group, Param};
+use crate::reader::{item::ConfigItem, Kind, OutputRules};
+use crate::render::write;
+
+const DRIVER: &str = "run_all";
+/// demographics and trial design domains run before other SDTM domains
+const SDTM_FIRST: [&str; 8] = ["dm", "ta", "te", "tv", "ti", "ts", "td", "tm"];
+const ADSL: &str = "adsl";
+
+impl Generator {
+    /// ## render a batch driver including every program of kind in run order
+    ///
+    /// driver is named `run_all` for dev and `v-run_all` for qc, programs not requiring qc are left out of qc driver.
+    /// Driver is derived from configuration only, so it is rewritten on every call
+    pub fn driver(&self, mut dest: &Path, param: &Param) -> anyhow::Result<FileResult> {
+        if dest.is_file() {
+            dest = dest.parent().unwrap();
+        }
+        if !dest.exists() {
+            fs::create_dir_all(dest)?;
+        }
+        let kind = match self.kind {
+            Kind::SDTM => "SDTM",
+            Kind::ADAM => "ADaM",
+            Kind::TFL => "TFL",
+        };
+        let comment = param.engine.comment();
+        let mut lines = vec![format!(
+            "{} Run all {} programs of {} {}",
+            comment.open, kind, param.study, comment.close
+        )
+        .trim_end()
+        .to_string()];
+        for item in run_order(&self.items, &self.kind, &self.output_rules) {
+            if !item.qc_required && Group::Qc.eq(&param.group) {
+                continue;
+            }
+            let program = dest.join(filename(&item.name, &param.group, &param.engine));
+            lines.push(param.engine.include(&program.to_string_lossy()));
+        }
+        lines.push("".into());
+        let name = filename(DRIVER, &param.group, &param.engine);
+        let filepath = dest.join(&name);
+        let outcome = if filepath.exists() {
+            FileOutcome::Overwritten
+        } else {
+            FileOutcome::Created
+        };
+        write(&filepath, &lines.join("\n"), &param.output_profile())?;
+        Ok(FileResult { name, outcome })
+    }
+}
+
+/// ## sort config items in the order their programs should run
+///
+/// SDTM runs DM and trial design domains first, ADaM runs ADSL first and then follows declared dependencies,
+/// TFL is sorted by output number. Items keep configuration order otherwise
+fn run_order<'a>(items: &'a [ConfigItem], kind: &Kind, rules: &OutputRules) -> Vec<&'a ConfigItem> {
+    let mut items = items.iter().collect::<Vec<&ConfigItem>>();
+    match kind {
+        Kind::SDTM => items.sort_by_key(|item| {
+            SDTM_FIRST
+                .iter()
+                .position(|domain| item.name.eq(domain))
+                .unwrap_or(SDTM_FIRST.len())
+        }),
+        Kind::ADAM => items = dependency_order(items),
+        Kind::TFL => items.sort_by_cached_key(|item| {
+            let number = rules
+                .classify(&item.name, item.output)
+                .and_then(|output| output.number)
+                .map(|number| {
+                    number
+                        .split('.')
+                        .map(|n| n.parse::<u32>().unwrap_or_default())
+                        .collect::<Vec<u32>>()
+                });
+            // outputs without number run last
+            (number.is_none(), number)
+        }),
+    }
+    items
+}
+
+/// place each item after its dependencies, every item other than ADSL depends on ADSL.
+/// Items in a dependency cycle are appended in configuration order
+fn dependency_order(mut pending: Vec<&ConfigItem>) -> Vec<&ConfigItem> {
+    let names = pending
+        .iter()
+        .map(|item| item.name.as_str())
+        .collect::<HashSet<&str>>();
+    let mut placed: HashSet<&str> = HashSet::new();
+    let mut ordered = vec![];
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|item| {
+            let adsl = item.name.eq(ADSL) || !names.contains(ADSL) || placed.contains(ADSL);
+            adsl && item.depends.iter().all(|depend| {
+                depend.eq(&item.name)
+                    || !names.contains(depend.as_str())
+                    || placed.contains(depend.as_str())
+            })
+        });
+        match ready {
+            Some(n) => {
+                let item = pending.remove(n);
+                placed.insert(&item.name);
+                ordered.push(item);
+            }
+            None => {
+                ordered.append(&mut pending);
+            }
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::Engine, generator::tests::param, reader::OutputKind};
+
+    fn item(name: &str, qc_required: bool, depends: &[&str]) -> ConfigItem {
+        ConfigItem {
+            name: name.into(),
+            supp: false,
+            qc_required,
+            output: None,
+            depends: depends.iter().map(|depend| depend.to_string()).collect(),
+            keys: vec![],
+        }
+    }
+
+    fn names(items: Vec<&ConfigItem>) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sdtm_order_test() {
+        let items = vec![
+            item("ae", true, &[]),
+            item("ts", true, &[]),
+            item("lb", true, &[]),
+            item("dm", true, &[]),
+        ];
+        let order = run_order(&items, &Kind::SDTM, &OutputRules::default());
+        assert_eq!(vec!["dm", "ts", "ae", "lb"], names(order));
+    }
+
+    #[test]
+    fn adam_order_test() {
+        let items = vec![
+            item("adtte", true, &["adrs", "adsl"]),
+            item("adrs", true, &["rs"]),
+            item("adsl", true, &["dm"]),
+            item("adae", true, &[]),
+            item("adx", true, &["ady"]),
+            item("ady", true, &["adx"]),
+        ];
+        let order = run_order(&items, &Kind::ADAM, &OutputRules::default());
+        assert_eq!(
+            vec!["adsl", "adrs", "adtte", "adae", "adx", "ady"],
+            names(order)
+        );
+    }
+
+    #[test]
+    fn tfl_order_test() {
+        let mut km = item("km", true, &[]);
+        km.output = Some(OutputKind::Figure);
+        let items = vec![
+            item("t-14-1-10-ae", true, &[]),
+            km,
+            item("l-16-2-1-dm", true, &[]),
+            item("t-14-1-9-ae", true, &[]),
+        ];
+        let order = run_order(&items, &Kind::TFL, &OutputRules::default());
+        assert_eq!(
+            vec!["t-14-1-9-ae", "t-14-1-10-ae", "l-16-2-1-dm", "km"],
+            names(order)
+        );
+    }
+
+    #[test]
+    fn driver_test() -> anyhow::Result<()> {
+        let items = vec![item("ae", false, &[]), item("dm", true, &[])];
+        let g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        let dest = tempfile::tempdir()?;
+        let mut param = param(Engine::SAS, Group::Dev, "");
+        let result = g.driver(dest.path(), &param)?;
+        assert_eq!("run_all.sas", result.name);
+        let content = fs::read_to_string(dest.path().join("run_all.sas"))?;
+        let lines = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .collect::<Vec<&str>>();
+        assert_eq!("/* Run all SDTM programs of AK112-303 */", lines[0]);
+        assert_eq!(
+            format!("%include \"{}\";", dest.path().join("dm.sas").display()),
+            lines[1]
+        );
+        assert_eq!(3, lines.len());
+
+        param.group = Group::Qc;
+        param.engine = Engine::R;
+        g.driver(dest.path(), &param)?;
+        let content = fs::read_to_string(dest.path().join("v-run_all.R"))?;
+        assert_eq!(2, content.lines().count());
+        assert!(content.starts_with("# Run all SDTM programs of AK112-303\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::{fs, path::Path};
+
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tera::{Context, Tera};
+
+use super::{FileResult, Param};
+use crate::{clock::Clock, reader::Kind, template::Version};
+
+/// standalone page, escaped as it contains user given text
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Generation report {{ report.timestamp }}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #eee; }
+.failed { color: #b00020; }
+</style>
+</head>
+<body>
+<h1>Generation report</h1>
+<table>
+{% for row in summary %}<tr><th>{{ row.0 }}</th><td>{{ row.1 }}</td></tr>
+{% endfor %}</table>
+<table>
+<tr><th>File</th><th>Outcome</th><th>Reason</th></tr>
+{% for file in files %}<tr class="{{ file.outcome }}"><td>{{ file.name }}</td><td>{{ file.outcome }}</td><td>{{ file.reason }}</td></tr>
+{% endfor %}</table>
+</body>
+</html>
+"#;
+
+/// what happened to a program of a generation run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FileOutcome {
+    Created,
+    /// program existed and was kept
+    Skipped,
+    /// program existed and was rewritten, such as batch drivers
+    Overwritten,
+    /// program was not written, with reason
+    Failed(String),
+}
+
+impl FileOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileOutcome::Created => "created",
+            FileOutcome::Skipped => "skipped",
+            FileOutcome::Overwritten => "overwritten",
+            FileOutcome::Failed(_) => "failed",
+        }
+    }
+    pub fn reason(&self) -> &str {
+        match self {
+            FileOutcome::Failed(reason) => reason,
+            _ => "",
+        }
+    }
+}
+
+/// specification or TOP a run is generated from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecFile {
+    pub path: String,
+    /// SHA-256 of file content in lower case hex
+    pub sha256: String,
+}
+
+impl SpecFile {
+    pub fn new(path: &Path) -> anyhow::Result<SpecFile> {
+        Ok(SpecFile {
+            path: path.to_string_lossy().to_string(),
+            sha256: format!("{:x}", Sha256::digest(fs::read(path)?)),
+        })
+    }
+}
+
+/// ## report of a generation run
+///
+/// exported as JSON, a standalone HTML page or an Excel workbook for the study file
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    /// time run started given by generator clock, in RFC 3339
+    pub timestamp: String,
+    pub kind: Kind,
+    /// spec file, none if generator is created from items
+    pub spec: Option<SpecFile>,
+    /// template version given by `Param::version`
+    pub version: Option<Version>,
+    /// `Param` of the run without template and custom code, which may be large
+    pub parameters: Value,
+    /// SHA-256 of template text in lower case hex
+    pub template_sha256: String,
+    pub files: Vec<FileResult>,
+}
+
+impl RunReport {
+    pub(crate) fn new(
+        kind: &Kind,
+        spec: Option<&Path>,
+        param: &Param,
+        files: Vec<FileResult>,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<RunReport> {
+        let mut parameters = serde_json::to_value(param)?;
+        if let Value::Object(parameters) = &mut parameters {
+            parameters.remove("template");
+            parameters.remove("custom_code");
+        }
+        Ok(RunReport {
+            timestamp: clock.now().to_rfc3339(),
+            kind: kind.clone(),
+            spec: spec.map(SpecFile::new).transpose()?,
+            version: param.version.clone(),
+            parameters,
+            template_sha256: format!("{:x}", Sha256::digest(param.template.as_bytes())),
+            files,
+        })
+    }
+
+    /// whether every program is created or kept
+    pub fn passed(&self) -> bool {
+        !self
+            .files
+            .iter()
+            .any(|file| matches!(file.outcome, FileOutcome::Failed(_)))
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_html(&self) -> anyhow::Result<String> {
+        let mut ctx = Context::new();
+        ctx.insert("report", self);
+        ctx.insert("summary", &self.summary());
+        ctx.insert("files", &self.rows());
+        Ok(Tera::one_off(HTML_TEMPLATE, &ctx, true)?)
+    }
+
+    /// save as workbook with a `Summary` and a `Files` sheet
+    pub fn save_xlsx(&self, dest: &Path) -> anyhow::Result<()> {
+        let bold = Format::new().set_bold();
+        let mut workbook = Workbook::new();
+        let summary = workbook.add_worksheet();
+        summary.set_name("Summary")?;
+        summary.set_column_width(0, 20)?;
+        summary.set_column_width(1, 80)?;
+        for (row, (name, value)) in self.summary().iter().enumerate() {
+            summary.write_string_with_format(row as u32, 0, name, &bold)?;
+            summary.write_string(row as u32, 1, value)?;
+        }
+        let files = workbook.add_worksheet();
+        files.set_name("Files")?;
+        files.set_column_width(0, 40)?;
+        files.set_column_width(1, 14)?;
+        files.set_column_width(2, 80)?;
+        for (col, header) in ["File", "Outcome", "Reason"].iter().enumerate() {
+            files.write_string_with_format(0, col as u16, *header, &bold)?;
+        }
+        for (row, file) in self.rows().iter().enumerate() {
+            let row = row as u32 + 1;
+            files.write_string(row, 0, file.name)?;
+            files.write_string(row, 1, file.outcome)?;
+            files.write_string(row, 2, file.reason)?;
+        }
+        workbook.save(dest)?;
+        Ok(())
+    }
+
+    /// name and value pairs of run, parameters are listed by name
+    fn summary(&self) -> Vec<(String, String)> {
+        let mut summary = vec![
+            ("timestamp".to_string(), self.timestamp.clone()),
+            ("kind".into(), format!("{:?}", self.kind)),
+        ];
+        if let Some(spec) = &self.spec {
+            summary.push(("spec".into(), spec.path.clone()));
+            summary.push(("spec sha256".into(), spec.sha256.clone()));
+        }
+        if let Some(Version {
+            name,
+            role,
+            offical,
+        }) = &self.version
+        {
+            let scope = if *offical { "offical" } else { "private" };
+            summary.push(("version".into(), format!("{}.{} ({})", role, name, scope)));
+        }
+        summary.push(("template sha256".into(), self.template_sha256.clone()));
+        if let Value::Object(parameters) = &self.parameters {
+            for (name, value) in parameters {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                summary.push((name.clone(), value));
+            }
+        }
+        summary
+    }
+
+    fn rows(&self) -> Vec<Row<'_>> {
+        self.files
+            .iter()
+            .map(|file| Row {
+                name: &file.name,
+                outcome: file.outcome.name(),
+                reason: file.outcome.reason(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct Row<'a> {
+    name: &'a str,
+    outcome: &'static str,
+    reason: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{
+        clock::FixedClock,
+        generator::{tests::param, Generator, Group},
+        reader::ConfigItem,
+        Engine,
+    };
+
+    #[test]
+    fn spec_file_test() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let spec = dir.path().join("spec.xlsx");
+        fs::write(&spec, "abc")?;
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            SpecFile::new(&spec)?.sha256
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn run_report_test() -> anyhow::Result<()> {
+        let items = ["ae", "suppae", "dm"]
+            .iter()
+            .map(|name| ConfigItem {
+                name: name.to_string(),
+                supp: false,
+                qc_required: true,
+                output: None,
+                depends: vec![],
+                keys: vec![],
+            })
+            .collect();
+        let mut g = Generator::from_items(items, Kind::SDTM, vec![])?;
+        g.set_clock(FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()));
+        let dest = tempfile::tempdir()?;
+        fs::write(dest.path().join("dm.sas"), "kept")?;
+        let mut param = Param {
+            version: Some(Version {
+                name: "v1".into(),
+                role: "dev".into(),
+                offical: true,
+            }),
+            ..param(
+                Engine::SAS,
+                Group::Dev,
+                "{{ item.name | sas_name(max=2) }} <&>",
+            )
+        };
+        let report = g.run(dest.path(), &param)?;
+        assert!(!report.passed());
+        let outcomes = report
+            .files
+            .iter()
+            .map(|file| file.outcome.name())
+            .collect::<Vec<&str>>();
+        assert_eq!(vec!["failed", "failed", "skipped"], outcomes);
+        assert!(report.files[1]
+            .outcome
+            .reason()
+            .contains("exceeds 2 characters"));
+        assert!(!dest.path().join("ae.sas").exists());
+
+        param.template = "{{ item.name }}".into();
+        let report = g.run(dest.path(), &param)?;
+        assert!(report.passed());
+        assert_eq!(FileOutcome::Created, report.files[0].outcome);
+        assert!(report.files[2].existed());
+
+        let json: Value = serde_json::from_str(&report.to_json()?)?;
+        assert_eq!("2024-03-05T00:00:00+00:00", json["timestamp"]);
+        assert_eq!("AK112-303", json["parameters"]["study"]);
+        assert!(json["parameters"].get("template").is_none());
+        assert!(json["parameters"].get("custom_code").is_none());
+        assert_eq!("Created", json["files"][0]["outcome"]);
+        assert_eq!(false, json["files"][0]["existed"]);
+        assert_eq!(true, json["files"][2]["existed"]);
+        let html = report.to_html()?;
+        assert!(html.contains("<td>dev.v1 (offical)</td>"));
+        assert!(html.contains("<td>suppae.sas</td><td>created</td>"));
+        let xlsx = dest.path().join("report.xlsx");
+        report.save_xlsx(&xlsx)?;
+        assert!(xlsx.exists());
+        Ok(())
+    }
+}
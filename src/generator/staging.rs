@@ -0,0 +1,108 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::Context;
+
+/// sequence of staging directories in this process, so concurrent batches into one destination do not share one
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// ## programs of a batch rendered into a staging directory of destination
+///
+/// files are moved into place only after every program rendered, if moving fails,
+/// files already moved are removed so destination is left as it was before the batch
+pub(crate) struct Staging {
+    dest: PathBuf,
+    directory: PathBuf,
+    files: Vec<String>,
+}
+
+impl Staging {
+    pub fn new(dest: &Path) -> anyhow::Result<Staging> {
+        let directory = dest.join(format!(
+            ".scaffold-staging-{}-{}",
+            std::process::id(),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&directory)?;
+        Ok(Staging {
+            dest: dest.into(),
+            directory,
+            files: vec![],
+        })
+    }
+
+    /// path a file is staged at before moved to destination
+    pub fn path(&mut self, filename: &str) -> PathBuf {
+        self.files.push(filename.into());
+        self.directory.join(filename)
+    }
+
+    /// move staged files into destination, rolling back moved ones on failure
+    pub fn commit(self) -> anyhow::Result<()> {
+        let mut moved: Vec<&str> = vec![];
+        for filename in &self.files {
+            let staged = self.directory.join(filename);
+            if !staged.exists() {
+                continue;
+            }
+            if let Err(e) = fs::rename(&staged, self.dest.join(filename)) {
+                for filename in moved {
+                    fs::remove_file(self.dest.join(filename)).ok();
+                }
+                return Err(e)
+                    .with_context(|| format!("Failed to move {}, batch is rolled back", filename));
+            }
+            moved.push(filename);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.directory).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staging_test() -> anyhow::Result<()> {
+        let dest = tempfile::tempdir()?;
+        let mut staging = Staging::new(dest.path())?;
+        fs::write(staging.path("ae.sas"), "ae")?;
+        assert!(!dest.path().join("ae.sas").exists());
+        staging.commit()?;
+        assert_eq!("ae", fs::read_to_string(dest.path().join("ae.sas"))?);
+
+        let mut staging = Staging::new(dest.path())?;
+        fs::write(staging.path("cm.sas"), "cm")?;
+        fs::write(staging.path("dm.sas"), "dm")?;
+        // a directory in place of dm.sas makes moving fail
+        fs::create_dir_all(dest.path().join("dm.sas").join("locked"))?;
+        assert!(staging.commit().is_err());
+        assert!(!dest.path().join("cm.sas").exists());
+        assert_eq!(2, fs::read_dir(dest.path())?.count());
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_staging_test() -> anyhow::Result<()> {
+        let dest = tempfile::tempdir()?;
+        let mut first = Staging::new(dest.path())?;
+        let mut second = Staging::new(dest.path())?;
+        fs::write(first.path("ae.sas"), "ae")?;
+        fs::write(second.path("cm.sas"), "cm")?;
+        first.commit()?;
+        second.commit()?;
+        assert_eq!("ae", fs::read_to_string(dest.path().join("ae.sas"))?);
+        assert_eq!("cm", fs::read_to_string(dest.path().join("cm.sas"))?);
+        assert_eq!(2, fs::read_dir(dest.path())?.count());
+        Ok(())
+    }
+}
@@ -1,11 +1,41 @@
+mod archive;
+mod audit;
+mod clock;
+mod clone;
+mod engine;
 mod generator;
 mod reader;
 mod render;
+mod setup;
 mod skeleton;
 mod template;
 
-pub use generator::{Assignment, FileResult, Generator, Group, Param};
-pub use reader::list_projects;
-pub use reader::{new_reader, read_assignment_from_top, ConfigItem, Kind};
-pub use skeleton::{Builder, DocumentSkeleton, StatSkeleton, STAT};
-pub use template::{Version, VersionManager, VersionManagerParam};
+pub use archive::{
+    archive, verify_archive, ArchiveManifest, ArchiveOption, ArchiveVerification, ManifestEntry,
+    MANIFEST,
+};
+pub use audit::{audit, AuditOption, ComplianceIssue, ComplianceKind, ComplianceReport};
+pub use clock::{Clock, DateFormat, FixedClock, SystemClock};
+pub use clone::{clone_purpose, CloneParam, CloneReport, ClonedProgram, HeaderLabels};
+pub use engine::{Comment, Engine};
+pub use generator::{
+    Assignment, CompareParam, FileOutcome, FileResult, Generator, Group, Param, RunReport,
+    SpecFile, StudyMetadata,
+};
+pub use reader::{list_projects, DiscoveryProfile};
+pub use reader::{
+    new_reader, read_assignment_from_top, ConfigItem, Kind, Output, OutputKind, OutputPrefix,
+    OutputRules,
+};
+pub use render::{Encoding, EncodingIssue, LineEnding, OutputProfile};
+pub use setup::{
+    default_library_rules, Library, LibraryRule, SetupGenerator, SetupParam, SetupProgram,
+    SetupTemplate,
+};
+pub use skeleton::{
+    Builder, CustomSkeleton, DocumentSkeleton, SeedFile, Skeleton, SkeletonDiff, SkeletonFormat,
+    SkeletonRegistry, StatSkeleton, SKELETON_SCHEMA, STAT,
+};
+pub use template::{
+    LintIssue, LintKind, LintOption, LintReport, Version, VersionManager, VersionManagerParam,
+};
@@ -7,13 +7,15 @@ use self::{adam_spec::AdamSpecReader, sdtm_spec::SdtmSpecReader, top::TopReader}
 mod adam_spec;
 mod errors;
 pub mod item;
+mod output;
 mod project;
 mod reader;
 mod sdtm_spec;
 pub mod top;
 
 pub use self::item::ConfigItem;
-pub use self::project::list_projects;
+pub use self::output::{Output, OutputKind, OutputPrefix, OutputRules};
+pub use self::project::{list_projects, DiscoveryProfile};
 pub use self::reader::Kind;
 
 pub fn new_reader(kind: &Kind, filepath: &Path) -> Box<dyn reader::ConfigReader> {
@@ -1,10 +1,15 @@
-use super::{item::ConfigItem, reader::ConfigReader};
+use super::{
+    item::ConfigItem,
+    reader::{find_column, split_list, ConfigReader},
+};
 use calamine::{open_workbook, DataType::Empty, Reader, Xlsx};
 use std::path::{Path, PathBuf};
 
 const CONTENT: &str = "CONTENT";
 const DOMAIN_COL_INDEX: usize = 0;
 const TARGET_ROWS_START_INDEX: usize = 6;
+const DEPENDS_HEADERS: [&str; 4] = ["Depends On", "Dependency", "Dependencies", "Input Datasets"];
+const KEYS_HEADERS: [&str; 3] = ["Key Variables", "Keys", "Key"];
 
 pub struct AdamSpecReader {
     filepath: PathBuf,
@@ -24,11 +29,15 @@ impl ConfigReader for AdamSpecReader {
         let mut workbook: Xlsx<_> = open_workbook(self.filepath.as_path())?;
         let supp = false;
         let qc_required = true;
+        let mut depends_col = None;
+        let mut keys_col = None;
 
         let range = workbook.worksheet_range(CONTENT)?;
         for (n, row) in range.rows().into_iter().enumerate() {
-            // skipping untarget rows
+            // skipping untarget rows, header locates in one of them
             if n < TARGET_ROWS_START_INDEX {
+                depends_col = depends_col.or(find_column(row, &DEPENDS_HEADERS));
+                keys_col = keys_col.or(find_column(row, &KEYS_HEADERS));
                 continue;
             }
             let domain;
@@ -40,12 +49,44 @@ impl ConfigReader for AdamSpecReader {
             } else {
                 break;
             }
+            let depends = depends_col
+                .and_then(|col| row.get(col))
+                .and_then(|cell| cell.as_string())
+                .map(|cell| depends(&cell))
+                .unwrap_or_default();
+            let keys = keys_col
+                .and_then(|col| row.get(col))
+                .and_then(|cell| cell.as_string())
+                .map(|cell| split_list(&cell.to_uppercase()))
+                .unwrap_or_default();
             domains.push(ConfigItem {
                 name: domain.to_lowercase(),
                 supp,
                 qc_required,
+                output: None,
+                depends,
+                keys,
             });
         }
         Ok(domains)
     }
 }
+
+/// split dependency declaration such as `ADSL, ADLB; SDTM.LB` into lowercase dataset names,
+/// library prefix is dropped
+fn depends(cell: &str) -> Vec<String> {
+    split_list(cell)
+        .iter()
+        .map(|name| name.rsplit('.').next().unwrap_or(name).to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn depends_test() {
+        assert_eq!(vec!["adsl", "adlb", "lb"], depends("ADSL, ADLB;\nSDTM.LB"));
+        assert!(depends("").is_empty());
+    }
+}
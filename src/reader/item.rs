@@ -1,8 +1,16 @@
 use serde::Serialize;
 
+use super::output::OutputKind;
+
 #[derive(Debug, Serialize)]
 pub struct ConfigItem {
     pub name: String,
     pub supp: bool,
     pub qc_required: bool,
+    /// output type declared in configuration file, only TOP declares it
+    pub output: Option<OutputKind>,
+    /// datasets this item is derived from, only ADaM specification declares it
+    pub depends: Vec<String>,
+    /// key variables of dataset declared in specification, upper case
+    pub keys: Vec<String>,
 }
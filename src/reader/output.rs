@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+const NAME_SEPARATORS: [char; 2] = ['-', '_'];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputKind {
+    Table,
+    Figure,
+    Listing,
+}
+
+impl OutputKind {
+    /// parse output type declared in TOP, such as `Table`, `t` or `Listings`
+    pub fn parse(value: &str) -> Option<OutputKind> {
+        let value = value.trim().to_lowercase();
+        if value.is_empty() {
+            return None;
+        }
+        if "tables".starts_with(&value) {
+            Some(OutputKind::Table)
+        } else if "figures".starts_with(&value) || "graphs".starts_with(&value) {
+            Some(OutputKind::Figure)
+        } else if "listings".starts_with(&value) {
+            Some(OutputKind::Listing)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputKind::Table => "table",
+            OutputKind::Figure => "figure",
+            OutputKind::Listing => "listing",
+        }
+    }
+}
+
+/// structured output information exposed to templates
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Output {
+    pub kind: OutputKind,
+    /// number of output, such as `14.1.1` for `t-14-1-1-dm`
+    pub number: Option<String>,
+    /// name of output without its type prefix
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutputPrefix {
+    pub prefix: String,
+    pub kind: OutputKind,
+}
+
+/// rules to classify an output by prefix of its name, used when TOP does not declare output type
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutputRules {
+    pub prefixes: Vec<OutputPrefix>,
+}
+
+impl Default for OutputRules {
+    fn default() -> Self {
+        OutputRules {
+            prefixes: vec![
+                OutputPrefix {
+                    prefix: "t".into(),
+                    kind: OutputKind::Table,
+                },
+                OutputPrefix {
+                    prefix: "f".into(),
+                    kind: OutputKind::Figure,
+                },
+                OutputPrefix {
+                    prefix: "l".into(),
+                    kind: OutputKind::Listing,
+                },
+            ],
+        }
+    }
+}
+
+impl OutputRules {
+    /// ## classify an output
+    ///
+    /// ### Arguments
+    ///
+    /// @ name: &str - name of output, such as `t-14-01-01-dm`
+    ///
+    /// @ declared: Option<OutputKind> - output type read from TOP, takes precedence over prefix rules
+    pub fn classify(&self, name: &str, declared: Option<OutputKind>) -> Option<Output> {
+        let matched = self.match_prefix(name);
+        let kind = declared.or(matched.as_ref().map(|(kind, _)| *kind))?;
+        let title = match matched {
+            Some((_, rest)) => rest,
+            None => name,
+        };
+        Some(Output {
+            kind,
+            number: number(title),
+            title: title.into(),
+        })
+    }
+
+    /// prefix only matches when followed by a separator, so `tte-km` is not a table
+    fn match_prefix<'a>(&self, name: &'a str) -> Option<(OutputKind, &'a str)> {
+        let lower = name.to_lowercase();
+        self.prefixes
+            .iter()
+            .find_map(|OutputPrefix { prefix, kind }| {
+                let prefix = prefix.to_lowercase();
+                let rest = lower.strip_prefix(&prefix)?;
+                if rest.starts_with(NAME_SEPARATORS) && rest.len() > 1 {
+                    name.get(prefix.len() + 1..).map(|rest| (*kind, rest))
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+/// leading numeric segments of name, joined with dot
+fn number(name: &str) -> Option<String> {
+    let segments = name
+        .split(NAME_SEPARATORS)
+        .take_while(|segment| !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()))
+        .collect::<Vec<&str>>();
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn classify_test() {
+        let rules = OutputRules::default();
+        let output = rules.classify("l-16-02-07-06-irae-ss", None).unwrap();
+        assert_eq!(OutputKind::Listing, output.kind);
+        assert_eq!(Some("16.02.07.06".into()), output.number);
+        assert_eq!("16-02-07-06-irae-ss", output.title);
+
+        assert_eq!(None, rules.classify("tte-km", None));
+        assert_eq!(None, rules.classify("t", None));
+        assert_eq!(None, rules.classify("t-", None));
+
+        let output = rules.classify("tte-km", Some(OutputKind::Figure)).unwrap();
+        assert_eq!(OutputKind::Figure, output.kind);
+        assert_eq!(None, output.number);
+        assert_eq!("tte-km", output.title);
+
+        let output = rules.classify("T_14_1_1", Some(OutputKind::Table)).unwrap();
+        assert_eq!(Some("14.1.1".into()), output.number);
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(Some(OutputKind::Table), OutputKind::parse("Table"));
+        assert_eq!(Some(OutputKind::Figure), OutputKind::parse(" f "));
+        assert_eq!(Some(OutputKind::Figure), OutputKind::parse("Graph"));
+        assert_eq!(Some(OutputKind::Listing), OutputKind::parse("LISTINGS"));
+        assert_eq!(None, OutputKind::parse(""));
+        assert_eq!(None, OutputKind::parse("dataset"));
+    }
+}
@@ -1,63 +1,194 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
+use anyhow::anyhow;
 use regex::Regex;
-use serde::Serialize;
-
-const STAT: &str = "stats";
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
 pub struct Product {
-    id: String,
-    name: String,
-    trials: Vec<Trial>,
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) trials: Vec<Trial>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct Trial {
-    id: String,
-    name: String,
-    purpose: Vec<Purpose>,
+    /// product, folders matched by `*` and trial joined by `-`, such as `ABC-1234-oncology-301`
+    pub(crate) id: String,
+    pub(crate) name: String,
+    /// trial directory relative to root
+    pub(crate) path: PathBuf,
+    pub(crate) purpose: Vec<Purpose>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct Purpose {
-    id: String,
-    name: String,
+    pub(crate) id: String,
+    pub(crate) name: String,
+    /// purpose directory relative to trial directory, such as `stats/CSR`
+    pub(crate) path: PathBuf,
+}
+
+/// ## how products, trials and purposes are found below root of studies
+///
+/// default profile finds `ak101/202/stats/CSR`, other business units may use names such as `ABC-1234`
+/// or an extra level, e.g. layout `{product}/*/{trial}/stats/{purpose}`.
+/// Folders matched by `*` are part of ids, so trials of the same name under different folders differ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryProfile {
+    /// regex product folders must match, `^ak\d{3}$` by default
+    pub product_pattern: String,
+    /// regex trial folders must match, every folder by default
+    pub trial_pattern: String,
+    /// levels from root to purpose separated by `/`, `{purpose}` must be the last level.
+    /// `*` matches any folder, other names must match exactly
+    pub layout: String,
+}
+
+impl Default for DiscoveryProfile {
+    fn default() -> Self {
+        DiscoveryProfile {
+            product_pattern: r"^ak\d{3}$".into(),
+            trial_pattern: ".*".into(),
+            layout: "{product}/{trial}/stats/{purpose}".into(),
+        }
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum Level {
+    Product,
+    Trial,
+    Purpose,
+    Any,
+    Folder(String),
+}
+
+impl DiscoveryProfile {
+    pub fn list_projects(&self, root: &Path) -> anyhow::Result<Vec<Product>> {
+        let product_pattern = Regex::new(&self.product_pattern)?;
+        let trial_pattern = Regex::new(&self.trial_pattern)?;
+        let levels = self.levels()?;
+        // positions are checked by `levels`
+        let find = |target: Level| levels.iter().position(|level| *level == target).unwrap();
+        let (product_level, trial_level) = (find(Level::Product), find(Level::Trial));
+        let mut products = vec![];
+        for (dir, wildcards) in descend(root, &levels[..product_level])? {
+            for product in list_folders(&dir)? {
+                if !product_pattern.is_match(&product) {
+                    continue;
+                }
+                let product_id = join_id(&wildcards, &product);
+                let product_dir = dir.join(&product);
+                let mut trials = vec![];
+                let trial_levels = &levels[product_level + 1..trial_level];
+                for (dir, wildcards) in descend(&product_dir, trial_levels)? {
+                    for trial in list_folders(&dir)? {
+                        if !trial_pattern.is_match(&trial) {
+                            continue;
+                        }
+                        let trial_id = format!("{}-{}", &product_id, join_id(&wildcards, &trial));
+                        let mut purposes = vec![];
+                        let trial_dir = dir.join(&trial);
+                        let purpose_levels = &levels[trial_level + 1..levels.len() - 1];
+                        for (dir, wildcards) in descend(&trial_dir, purpose_levels)? {
+                            for purpose in list_folders(&dir)? {
+                                purposes.push(Purpose {
+                                    id: format!("{}-{}", &trial_id, join_id(&wildcards, &purpose)),
+                                    path: dir.strip_prefix(&trial_dir)?.join(&purpose),
+                                    name: purpose,
+                                });
+                            }
+                        }
+                        trials.push(Trial {
+                            id: trial_id,
+                            name: trial,
+                            path: trial_dir.strip_prefix(root)?.to_path_buf(),
+                            purpose: purposes,
+                        });
+                    }
+                }
+                products.push(Product {
+                    id: product_id,
+                    name: product,
+                    trials,
+                })
+            }
+        }
+        Ok(products)
+    }
+
+    /// levels of layout, `{product}`, `{trial}` and `{purpose}` appear once and in order
+    fn levels(&self) -> anyhow::Result<Vec<Level>> {
+        let levels = self
+            .layout
+            .split(['/', '\\'])
+            .filter(|level| !level.is_empty())
+            .map(|level| match level {
+                "{product}" => Ok(Level::Product),
+                "{trial}" => Ok(Level::Trial),
+                "{purpose}" => Ok(Level::Purpose),
+                "*" => Ok(Level::Any),
+                level if level.contains(['{', '}', '*']) => {
+                    Err(anyhow!("Unknown level {} in layout {}", level, self.layout))
+                }
+                level => Ok(Level::Folder(level.into())),
+            })
+            .collect::<anyhow::Result<Vec<Level>>>()?;
+        let placeholders = levels
+            .iter()
+            .filter(|level| matches!(level, Level::Product | Level::Trial | Level::Purpose))
+            .collect::<Vec<&Level>>();
+        if placeholders != [&Level::Product, &Level::Trial, &Level::Purpose]
+            || levels.last() != Some(&Level::Purpose)
+        {
+            return Err(anyhow!(
+                "Layout {} must contain {{product}}, {{trial}} and {{purpose}} once and in order, with {{purpose}} last",
+                self.layout
+            ));
+        }
+        Ok(levels)
+    }
+}
+
+/// find products, trials and purposes with the default `DiscoveryProfile`
 pub fn list_projects(root: &Path) -> anyhow::Result<Vec<Product>> {
-    let pattern = Regex::new(r"^ak\d{3}$")?;
-    let mut products = vec![];
-    let product_list = list_folders(root)?
-        .into_iter()
-        .filter(|dir| pattern.is_match(dir))
-        .collect::<Vec<String>>();
-    for product in product_list {
-        let product_dir = root.join(&product);
-        let mut trials = vec![];
-        for trial in list_folders(&product_dir)? {
-            let trail_id = format!("{}-{}", &product, &trial);
-            let purposes = list_folders(product_dir.join(&trial).join(STAT).as_path())?;
-            let trial = Trial {
-                id: trail_id.clone(),
-                name: trial,
-                purpose: purposes
-                    .into_iter()
-                    .map(|p| Purpose {
-                        id: format!("{}-{}", &trail_id, &p),
-                        name: p,
-                    })
-                    .collect::<Vec<Purpose>>(),
-            };
-            trials.push(trial);
+    DiscoveryProfile::default().list_projects(root)
+}
+
+/// directories below dir matching fixed levels and wildcards, with folders matched by wildcards
+fn descend(dir: &Path, levels: &[Level]) -> anyhow::Result<Vec<(PathBuf, Vec<String>)>> {
+    let mut dirs = vec![(dir.to_path_buf(), vec![])];
+    for level in levels {
+        let mut next = vec![];
+        for (dir, wildcards) in dirs {
+            match level {
+                Level::Folder(name) => next.push((dir.join(name), wildcards)),
+                _ => {
+                    for folder in list_folders(&dir)? {
+                        let mut wildcards = wildcards.clone();
+                        wildcards.push(folder.clone());
+                        next.push((dir.join(folder), wildcards));
+                    }
+                }
+            }
         }
-        products.push(Product {
-            id: product.clone(),
-            name: product,
-            trials,
-        })
+        dirs = next;
     }
-    Ok(products)
+    Ok(dirs)
+}
+
+/// folders matched by wildcards followed by name, joined by `-`
+fn join_id(wildcards: &[String], name: &str) -> String {
+    wildcards
+        .iter()
+        .map(|folder| folder.as_str())
+        .chain([name])
+        .collect::<Vec<&str>>()
+        .join("-")
 }
 
 fn list_folders(root: &Path) -> anyhow::Result<Vec<String>> {
@@ -73,6 +204,7 @@ fn list_folders(root: &Path) -> anyhow::Result<Vec<String>> {
         }
         folders.push(entry.file_name().to_string_lossy().to_string());
     }
+    folders.sort();
     Ok(folders)
 }
 
@@ -85,4 +217,71 @@ mod project_test {
         let projects = list_projects(root).unwrap();
         println!("{:?}", projects);
     }
+
+    #[test]
+    fn discovery_profile_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        for dir in [
+            "ABC-1234/oncology/301/stats/CSR",
+            "ABC-1234/oncology/302",
+            "ABC-1234/hematology/101/stats/DSUR",
+            "ABC-1234/hematology/301",
+            "abc-12/oncology/301/stats/CSR",
+            "ak101/202/stats/CSR",
+        ] {
+            fs::create_dir_all(root.path().join(dir))?;
+        }
+        let products = list_projects(root.path())?;
+        assert_eq!(1, products.len());
+        assert_eq!("ak101-202-CSR", products[0].trials[0].purpose[0].id);
+
+        let profile = DiscoveryProfile {
+            product_pattern: r"^[A-Z]{3}-\d{4}$".into(),
+            trial_pattern: r"^\d{3}$".into(),
+            layout: "{product}/*/{trial}/stats/{purpose}".into(),
+        };
+        let products = profile.list_projects(root.path())?;
+        assert_eq!(1, products.len());
+        let trials = products[0]
+            .trials
+            .iter()
+            .map(|trial| {
+                let purposes = trial.purpose.iter().map(|p| p.id.as_str());
+                (trial.id.as_str(), purposes.collect::<Vec<&str>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (
+                    "ABC-1234-hematology-101",
+                    vec!["ABC-1234-hematology-101-DSUR"]
+                ),
+                ("ABC-1234-hematology-301", vec![]),
+                ("ABC-1234-oncology-301", vec!["ABC-1234-oncology-301-CSR"]),
+                ("ABC-1234-oncology-302", vec![]),
+            ],
+            trials
+        );
+        assert_eq!(
+            Path::new("ABC-1234").join("oncology").join("301"),
+            products[0].trials[2].path
+        );
+        assert_eq!(
+            Path::new("stats").join("CSR"),
+            products[0].trials[2].purpose[0].path
+        );
+
+        for layout in [
+            "{trial}/{product}/{purpose}",
+            "{product}/{trial}/{purpose}/stats",
+            "{product}/{study}/{trial}/{purpose}",
+        ] {
+            let profile = DiscoveryProfile {
+                layout: layout.into(),
+                ..Default::default()
+            };
+            assert!(profile.list_projects(root.path()).is_err());
+        }
+        Ok(())
+    }
 }
@@ -1,8 +1,9 @@
-use serde::Deserialize;
+use calamine::DataType;
+use serde::{Deserialize, Serialize};
 
 use super::item::ConfigItem;
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Kind {
     SDTM,
     ADAM,
@@ -17,3 +18,24 @@ pub trait ConfigReader {
     /// @ force: bool - force to return config items even it contains errors, such as length of filename exceeds the limitation
     fn read(&self, force: bool) -> anyhow::Result<Vec<ConfigItem>>;
 }
+
+/// find index of column whose header matches one of names, ignoring case
+pub fn find_column(header: &[DataType], names: &[&str]) -> Option<usize> {
+    header.iter().position(|cell| {
+        cell.as_string()
+            .map(|cell| {
+                let cell = cell.trim();
+                names.iter().any(|name| name.eq_ignore_ascii_case(cell))
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// split a list declared in one cell, such as `STUDYID, USUBJID; AESEQ`
+pub fn split_list(cell: &str) -> Vec<String> {
+    cell.split([',', ';', ' ', '\n'])
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect()
+}
@@ -1,4 +1,7 @@
-use super::{item::ConfigItem, reader::ConfigReader};
+use super::{
+    item::ConfigItem,
+    reader::{find_column, split_list, ConfigReader},
+};
 use calamine::{open_workbook, DataType::Empty, Reader, Xlsx};
 use std::{
     collections::HashSet,
@@ -10,6 +13,7 @@ const SUPP_PREFIX: &str = "SUPP";
 const DOMAIN_COL_INDEX: usize = 0;
 const TARGET_ROWS_START_INDEX: usize = 6;
 const VAR_BELONG_COL_INDEX: usize = 9;
+const KEYS_HEADERS: [&str; 3] = ["Key Variables", "Keys", "Key"];
 
 pub struct SdtmSpecReader {
     filepath: PathBuf,
@@ -31,12 +35,14 @@ impl ConfigReader for SdtmSpecReader {
 
         // a hash set to record if content sheet records supplymental domain(record their main domain instead)
         let mut supp_exist: HashSet<String> = HashSet::new();
+        let mut keys_col = None;
 
         let range = workbook.worksheet_range(CONTENT)?;
 
         for (n, row) in range.rows().into_iter().enumerate() {
-            // skipping untarget rows
+            // skipping untarget rows, header locates in one of them
             if n < TARGET_ROWS_START_INDEX {
+                keys_col = keys_col.or(find_column(row, &KEYS_HEADERS));
                 continue;
             }
             let domain;
@@ -69,10 +75,18 @@ impl ConfigReader for SdtmSpecReader {
                 }
             }
 
+            let keys = keys_col
+                .and_then(|col| row.get(col))
+                .and_then(|cell| cell.as_string())
+                .map(|cell| split_list(&cell.to_uppercase()))
+                .unwrap_or_default();
             domains.push(ConfigItem {
                 name: domain.to_lowercase(),
                 supp,
                 qc_required,
+                output: None,
+                depends: vec![],
+                keys,
             });
         }
 
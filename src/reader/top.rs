@@ -3,7 +3,8 @@ use crate::Assignment;
 use super::{
     errors::{OutputError, OUTPUT_NAME_EXCEED},
     item::ConfigItem,
-    reader::ConfigReader,
+    output::OutputKind,
+    reader::{find_column, ConfigReader},
 };
 use anyhow::anyhow;
 use calamine::{open_workbook, DataType::Empty, Reader, Xlsx};
@@ -16,6 +17,7 @@ const TARGET_ROWS_START_INDEX: usize = 1;
 const MAX_EMPTY_ROW_COUNT: usize = 10;
 const SOURCER_COLUMN_INDEX: usize = 9;
 const QCER_COLUMN_INDEX: usize = 10;
+const OUTPUT_TYPE_HEADERS: [&str; 3] = ["Output Type", "Type", "TFL"];
 
 pub struct TopReader {
     filepath: PathBuf,
@@ -78,11 +80,15 @@ impl ConfigReader for TopReader {
         let supp = false;
         let mut qc_required = true;
         let mut error_info = vec![];
+        let mut output_type_col = None;
 
         let range = workbook.worksheet_range(TOP)?;
         for (n, row) in range.rows().into_iter().enumerate() {
             // skipping untarget rows
             if n < TARGET_ROWS_START_INDEX {
+                if n == 0 {
+                    output_type_col = find_column(row, &OUTPUT_TYPE_HEADERS);
+                }
                 continue;
             }
             let output;
@@ -114,10 +120,17 @@ impl ConfigReader for TopReader {
                     false
                 };
             }
+            let output_type = output_type_col
+                .and_then(|col| row.get(col))
+                .and_then(|cell| cell.as_string())
+                .and_then(|cell| OutputKind::parse(&cell));
             outputs.push(ConfigItem {
                 name: output.to_lowercase(),
                 supp,
                 qc_required,
+                output: output_type,
+                depends: vec![],
+                keys: vec![],
             });
         }
         if error_info.len() > 0 {
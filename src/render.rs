@@ -1,13 +1,27 @@
-use std::{fs, path::Path};
+use std::{collections::HashMap, fs, path::Path};
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tera::{Context, Tera};
 
+mod filters;
+mod profile;
+
+pub use profile::{Encoding, EncodingIssue, LineEnding, OutputProfile};
+
+use crate::{
+    engine::Comment,
+    generator::{Group, StudyMetadata},
+    reader::{ConfigItem, Output},
+    template::{lint, LintOption, LintReport, Version},
+};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Item {
     pub name: String,
     pub study: String,
     pub engine: String,
+    pub comment: Comment,
     pub purpose: String,
     pub start: String,
     pub description: String,
@@ -15,28 +29,94 @@ pub struct Item {
     pub developer: String,
     pub slot: Vec<String>,
     pub path: String,
+    pub output: Option<Output>,
+    /// production dataset QC programs compare against, only for qc
+    pub compare: Option<Compare>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Compare {
+    pub library: String,
+    pub dataset: String,
+    /// `library.dataset`, used as `base=` of `proc compare`
+    pub base: String,
+    /// path of production dataset file, empty if folder of production datasets is not given
+    pub path: String,
+    /// key variables for `id` statement
+    pub keys: Vec<String>,
+    pub options: Vec<String>,
 }
 
-pub struct Render {}
+/// everything a program template can use
+#[derive(Debug, Serialize)]
+pub struct Scope<'a> {
+    pub item: &'a Item,
+    /// config item read from specification or TOP
+    pub meta: &'a ConfigItem,
+    pub study: &'a StudyMetadata,
+    pub group: &'a Group,
+    pub version: Option<&'a Version>,
+    /// user defined variables
+    pub vars: &'a HashMap<String, Value>,
+}
+
+/// name template given by `Param` is registered under in template set
+const INLINE_TEMPLATE: &str = "__scaffold_inline";
+
+pub struct Render {
+    templates: Tera,
+}
 
 impl Render {
     pub fn new() -> anyhow::Result<Render> {
-        Ok(Render {})
+        Ok(Render::with_templates(Tera::default()))
+    }
+    /// render with a template set, such as `VersionManager::template_set`,
+    /// which templates can extend, include or import from
+    ///
+    /// programs are not HTML, so no template of set is escaped
+    pub fn with_templates(mut templates: Tera) -> Render {
+        templates.autoescape_on(vec![]);
+        filters::register(&mut templates);
+        Render { templates }
     }
     /// if file already existed before created, return true, else return false
-    pub fn render(&self, template: &str, item: &Item, dest: &Path) -> anyhow::Result<bool> {
+    pub fn render<T: Serialize>(
+        &self,
+        template: &str,
+        item: &T,
+        dest: &Path,
+        profile: &OutputProfile,
+    ) -> anyhow::Result<bool> {
+        let mut ctx = Context::new();
+        ctx.insert("item", item);
+        self.render_context(template, &ctx, dest, profile)
+    }
+    /// render with a prepared context, such as one built from `Scope`
+    pub fn render_context(
+        &self,
+        template: &str,
+        ctx: &Context,
+        dest: &Path,
+        profile: &OutputProfile,
+    ) -> anyhow::Result<bool> {
         if dest.exists() {
             return Ok(true);
         };
-        let mut ctx = Context::new();
-        ctx.insert("item", item);
-        let mut data = Tera::one_off(template, &ctx, true)?.into_bytes();
-        // add BOM
-        data.insert(0, 239);
-        data.insert(1, 187);
-        data.insert(2, 191);
-        fs::write(dest, data)?;
-        Ok(false)
+        self.prepare(template)?.render_context(ctx, dest, profile)
+    }
+    pub fn render_str(&self, template: &str, ctx: &Context) -> anyhow::Result<String> {
+        self.prepare(template)?.render_str(ctx)
+    }
+    /// ## register template into a copy of template set once, for rendering every item of a batch
+    pub fn prepare(&self, template: &str) -> anyhow::Result<Prepared> {
+        let mut templates = self.templates.clone();
+        templates.add_raw_template(INLINE_TEMPLATE, template)?;
+        Ok(Prepared { templates })
+    }
+    /// check template parses, references only variables of sample context and renders with it
+    pub fn lint(&self, template: &str, sample: &Context, option: &LintOption) -> LintReport {
+        lint::lint(self, template, sample, option)
     }
 
     // pub fn render(&self, template: &str, item: &Item, dest: &Path) -> anyhow::Result<bool> {
@@ -59,18 +139,52 @@ impl Render {
     // }
 }
 
+/// template set with a template of `Param` registered, see `Render::prepare`
+pub struct Prepared {
+    templates: Tera,
+}
+
+impl Prepared {
+    /// if file already existed before created, return true, else return false
+    pub fn render_context(
+        &self,
+        ctx: &Context,
+        dest: &Path,
+        profile: &OutputProfile,
+    ) -> anyhow::Result<bool> {
+        if dest.exists() {
+            return Ok(true);
+        };
+        let data = self.render_str(ctx)?;
+        write(dest, &data, profile)?;
+        Ok(false)
+    }
+    pub fn render_str(&self, ctx: &Context) -> anyhow::Result<String> {
+        Ok(self.templates.render(INLINE_TEMPLATE, ctx)?)
+    }
+}
+
+/// write content to dest in encoding, line ending and BOM of profile
+pub fn write(dest: &Path, content: &str, profile: &OutputProfile) -> anyhow::Result<()> {
+    fs::write(dest, profile.encode(content)?)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::Engine;
     use crate::render::Item;
 
     #[test]
     fn sdtm_template_test() {
         let sdtm = Render::new().unwrap();
+        let profile = OutputProfile::for_engine(&Engine::SAS);
         let item = Item {
             name: "lb".into(),
             study: "AK112-303".into(),
-            engine: "SAS EG".into(),
+            engine: "SAS".into(),
+            comment: Engine::SAS.comment(),
             purpose: "SDTM.LB".into(),
             start: "14MAR2023".into(),
             description: "Create".into(),
@@ -78,20 +192,24 @@ mod tests {
             developer: "yuki".into(),
             slot: vec!["%format".into(), "%checklog".into()],
             path: "".into(),
+            output: None,
+            compare: None,
         };
         let dest = Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\lb.sas");
-        sdtm.render("sdtm/dev.v1", &item, dest).unwrap();
+        sdtm.render("sdtm/dev.v1", &item, dest, &profile).unwrap();
         let dest = Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\v-lb.sas");
-        sdtm.render("sdtm/qc.v1", &item, dest).unwrap();
+        sdtm.render("sdtm/qc.v1", &item, dest, &profile).unwrap();
     }
 
     #[test]
     fn adam_template_test() {
         let sdtm = Render::new().unwrap();
+        let profile = OutputProfile::for_engine(&Engine::SAS);
         let item = Item {
             name: "adsl".into(),
             study: "AK112-303".into(),
-            engine: "SAS EG".into(),
+            engine: "SAS".into(),
+            comment: Engine::SAS.comment(),
             purpose: "ADAM.ADSL".into(),
             start: "14MAR2023".into(),
             description: "Create".into(),
@@ -99,20 +217,24 @@ mod tests {
             developer: "yuki".into(),
             slot: vec!["%format".into(), "%checklog".into()],
             path: "".into(),
+            output: None,
+            compare: None,
         };
         let dest = Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\adsl.sas");
-        sdtm.render("adam/dev.v1", &item, dest).unwrap();
+        sdtm.render("adam/dev.v1", &item, dest, &profile).unwrap();
         let dest = Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\v-adsl.sas");
-        sdtm.render("adam/qc.v1", &item, dest).unwrap();
+        sdtm.render("adam/qc.v1", &item, dest, &profile).unwrap();
     }
 
     #[test]
     fn tfl_template_test() {
         let sdtm = Render::new().unwrap();
+        let profile = OutputProfile::for_engine(&Engine::SAS);
         let item = Item {
             name: "l-16-02-07-06-irae-ss".into(),
             study: "AK112-303".into(),
-            engine: "SAS EG".into(),
+            engine: "SAS".into(),
+            comment: Engine::SAS.comment(),
             purpose: "xxxx".into(),
             start: "14MAR2023".into(),
             description: "Create".into(),
@@ -120,11 +242,31 @@ mod tests {
             developer: "yuki".into(),
             slot: vec!["%format".into(), "%checklog".into()],
             path: "".into(),
+            output: None,
+            compare: None,
         };
         let dest = Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\l-16-02-07-06-irae-ss.sas");
-        sdtm.render("tfls/dev.v1", &item, dest).unwrap();
+        sdtm.render("tfls/dev.v1", &item, dest, &profile).unwrap();
         let dest =
             Path::new(r"D:\projects\rusty\mobius_kit\.mocks\code\v-l-16-02-07-06-irae-ss.sas");
-        sdtm.render("tfls/qc.v1", &item, dest).unwrap();
+        sdtm.render("tfls/qc.v1", &item, dest, &profile).unwrap();
+    }
+
+    #[test]
+    fn prepare_test() -> anyhow::Result<()> {
+        let mut templates = Tera::default();
+        templates.add_raw_template("header.html", "/* {{ path }} */")?;
+        let render = Render::with_templates(templates);
+        let prepared =
+            render.prepare("{% include \"header.html\" %}\nlibname raw \"{{ path }}\";")?;
+        for path in [r"D:\data\<raw>", "/data/a&b"] {
+            let mut ctx = Context::new();
+            ctx.insert("path", path);
+            assert_eq!(
+                format!("/* {} */\nlibname raw \"{}\";", path, path),
+                prepared.render_str(&ctx)?
+            );
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use tera::{Error, Filter, Function, Result, Tera, Value};
+
+use crate::clock::DateFormat;
+
+const DEFAULT_WIDTH: usize = 80;
+const SAS_NAME_MAX: usize = 32;
+
+/// filters and functions producing program text, their output is never escaped
+struct Safe<F>(F);
+
+impl<F> Filter for Safe<F>
+where
+    F: Fn(&Value, &HashMap<String, Value>) -> Result<Value> + Sync + Send,
+{
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+        (self.0)(value, args)
+    }
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+impl<F> Function for Safe<F>
+where
+    F: Fn(&Value, &HashMap<String, Value>) -> Result<Value> + Sync + Send,
+{
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        (self.0)(&Value::Null, args)
+    }
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// register SAS aware filters and functions
+pub fn register(tera: &mut Tera) {
+    tera.register_filter("sas_comment_box", Safe(sas_comment_box));
+    tera.register_filter("pad", Safe(pad));
+    tera.register_filter("sas_name", Safe(sas_name));
+    tera.register_filter("upcase_ds", Safe(upcase_ds));
+    tera.register_filter("sas_date", Safe(sas_date));
+    tera.register_filter("wrap", Safe(wrap));
+    tera.register_function("sas_line", Safe(sas_line));
+}
+
+fn text(value: &Value, filter: &str) -> Result<String> {
+    match value {
+        Value::String(value) => Ok(value.to_owned()),
+        Value::Null => Ok("".into()),
+        Value::Number(_) | Value::Bool(_) => Ok(value.to_string()),
+        _ => Err(Error::msg(format!(
+            "Filter `{}` expects a string, got {}",
+            filter, value
+        ))),
+    }
+}
+
+fn usize_arg(args: &HashMap<String, Value>, name: &str, default: usize) -> Result<usize> {
+    match args.get(name) {
+        Some(value) => value
+            .as_u64()
+            .map(|value| value as usize)
+            .ok_or_else(|| Error::msg(format!("Argument `{}` should be a positive integer", name))),
+        None => Ok(default),
+    }
+}
+
+fn str_arg<'a>(args: &'a HashMap<String, Value>, name: &str) -> Option<&'a str> {
+    args.get(name).and_then(|value| value.as_str())
+}
+
+/// split text into lines no longer than width, breaking on whitespace where possible
+fn wrap_lines(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            while word.chars().count() > width {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let head = word.chars().take(width).collect::<String>();
+                word = word.chars().skip(width).collect();
+                lines.push(head);
+            }
+            if line.is_empty() {
+                line = word;
+            } else if line.chars().count() + 1 + word.chars().count() <= width {
+                line.push(' ');
+                line.push_str(&word);
+            } else {
+                lines.push(std::mem::replace(&mut line, word));
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `{{ text | sas_comment_box(width=80) }}`, wrap text in a box of `/* */` lines with fixed width
+fn sas_comment_box(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let width = usize_arg(args, "width", DEFAULT_WIDTH)?;
+    if width < 8 {
+        return Err(Error::msg("Width of sas_comment_box should be at least 8"));
+    }
+    let inner = width - 6;
+    let border = format!("/{}/", "*".repeat(width - 2));
+    let mut lines = vec![border.clone()];
+    for line in wrap_lines(&text(value, "sas_comment_box")?, inner) {
+        lines.push(format!("/* {:inner$} */", line, inner = inner));
+    }
+    lines.push(border);
+    Ok(Value::String(lines.join("\n")))
+}
+
+/// `{{ sas_line(width=80) }}`, a full comment line such as `/*****/`
+fn sas_line(_: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let width = usize_arg(args, "width", DEFAULT_WIDTH)?.max(4);
+    Ok(Value::String(format!("/{}/", "*".repeat(width - 2))))
+}
+
+/// `{{ item.developer | pad(n=27) }}`, pad text with trailing spaces to n characters
+fn pad(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let n = usize_arg(args, "n", 0)?;
+    Ok(Value::String(format!("{:n$}", text(value, "pad")?, n = n)))
+}
+
+/// `{{ item.name | sas_name(max=8) }}`, fail rendering if text is not a valid SAS name
+fn sas_name(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let max = usize_arg(args, "max", SAS_NAME_MAX)?;
+    let name = text(value, "sas_name")?;
+    let pattern = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").map_err(Error::msg)?;
+    if !pattern.is_match(&name) {
+        return Err(Error::msg(format!("`{}` is not a valid SAS name", name)));
+    }
+    if name.len() > max {
+        return Err(Error::msg(format!(
+            "`{}` exceeds {} characters of SAS name",
+            name, max
+        )));
+    }
+    Ok(Value::String(name))
+}
+
+/// `{{ item.name | upcase_ds(lib="sdtm") }}`, upper case dataset name with optional library, e.g. `SDTM.AE`
+fn upcase_ds(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let name = text(value, "upcase_ds")?.trim().to_uppercase();
+    Ok(Value::String(match str_arg(args, "lib") {
+        Some(lib) if !lib.is_empty() => format!("{}.{}", lib.to_uppercase(), name),
+        _ => name,
+    }))
+}
+
+/// `{{ item.start | sas_date(format="ISO8601") }}`, reformat a date written as ISO 8601 or `DDMONYYYY`,
+/// format is `DDMONYYYY`, `ISO8601` or a strftime pattern
+fn sas_date(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let date = text(value, "sas_date")?;
+    let date = date.trim();
+    let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%d%b%Y"))
+        .map_err(|_| Error::msg(format!("`{}` is not a date", date)))?;
+    let format = match str_arg(args, "format").unwrap_or("DDMONYYYY") {
+        "DDMONYYYY" => DateFormat::DDMONYYYY,
+        "ISO8601" => DateFormat::ISO8601,
+        pattern => DateFormat::Custom(pattern.into()),
+    };
+    format
+        .format(parsed)
+        .map(Value::String)
+        .map_err(|e| Error::msg(e.to_string()))
+}
+
+/// `{{ title | wrap(width=60, indent="   ") }}`, wrap long text into lines, following lines are indented
+fn wrap(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let width = usize_arg(args, "width", DEFAULT_WIDTH)?;
+    let indent = str_arg(args, "indent").unwrap_or("");
+    let lines = wrap_lines(&text(value, "wrap")?, width);
+    Ok(Value::String(lines.join(&format!("\n{}", indent))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::Context;
+
+    fn render(template: &str) -> Result<String> {
+        let mut tera = Tera::default();
+        register(&mut tera);
+        tera.autoescape_on(vec!["test"]);
+        tera.add_raw_template("test", template)?;
+        let mut ctx = Context::new();
+        ctx.insert("name", "ae");
+        ctx.insert("developer", "yuki");
+        ctx.insert("start", "05MAR2024");
+        tera.render("test", &ctx)
+    }
+
+    #[test]
+    fn sas_comment_box_test() {
+        assert_eq!(
+            "/**********/\n/* a b    */\n/* cde    */\n/**********/",
+            render("{{ 'a b cde' | sas_comment_box(width=12) }}").unwrap()
+        );
+        assert_eq!("/****/", render("{{ sas_line(width=6) }}").unwrap());
+    }
+
+    #[test]
+    fn pad_test() {
+        assert_eq!("yuki  |", render("{{ developer | pad(n=6) }}|").unwrap());
+        assert_eq!("yuki|", render("{{ developer | pad(n=2) }}|").unwrap());
+    }
+
+    #[test]
+    fn sas_name_test() {
+        assert_eq!("ae", render("{{ name | sas_name }}").unwrap());
+        assert!(render("{{ 'l-16-01' | sas_name }}").is_err());
+        assert!(render("{{ 'adsl_long' | sas_name(max=8) }}").is_err());
+    }
+
+    #[test]
+    fn upcase_ds_test() {
+        assert_eq!("AE", render("{{ name | upcase_ds }}").unwrap());
+        assert_eq!(
+            "SDTM.AE",
+            render("{{ name | upcase_ds(lib='sdtm') }}").unwrap()
+        );
+    }
+
+    #[test]
+    fn sas_date_test() {
+        assert_eq!(
+            "2024-03-05",
+            render("{{ start | sas_date(format='ISO8601') }}").unwrap()
+        );
+        assert_eq!(
+            "05MAR2024",
+            render("{{ '2024-03-05' | sas_date }}").unwrap()
+        );
+        assert!(render("{{ 'soon' | sas_date }}").is_err());
+    }
+
+    #[test]
+    fn wrap_test() {
+        assert_eq!(
+            "Summary of\n  Adverse\n  Events",
+            render("{{ 'Summary of Adverse Events' | wrap(width=10, indent='  ') }}").unwrap()
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+
+const BOM: [u8; 3] = [239, 187, 191];
+/// issues listed in error message of a failed encoding
+const MAX_REPORTED_ISSUES: usize = 10;
+/// characters windows-1252 places in 0x80..=0x9F, undefined positions are none
+const WINDOWS_1252_HIGH: [Option<char>; 32] = [
+    Some('\u{20AC}'),
+    None,
+    Some('\u{201A}'),
+    Some('\u{0192}'),
+    Some('\u{201E}'),
+    Some('\u{2026}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{02C6}'),
+    Some('\u{2030}'),
+    Some('\u{0160}'),
+    Some('\u{2039}'),
+    Some('\u{0152}'),
+    None,
+    Some('\u{017D}'),
+    None,
+    None,
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2022}'),
+    Some('\u{2013}'),
+    Some('\u{2014}'),
+    Some('\u{02DC}'),
+    Some('\u{2122}'),
+    Some('\u{0161}'),
+    Some('\u{203A}'),
+    Some('\u{0153}'),
+    None,
+    Some('\u{017E}'),
+    Some('\u{0178}'),
+];
+
+/// encoding of generated programs, named after SAS session encodings
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    UTF8,
+    /// ISO-8859-1
+    LATIN1,
+    /// windows-1252
+    WLATIN1,
+}
+
+impl Encoding {
+    fn encode_char(&self, c: char) -> Option<Vec<u8>> {
+        match self {
+            Encoding::UTF8 => Some(c.to_string().into_bytes()),
+            Encoding::LATIN1 => u8::try_from(u32::from(c)).ok().map(|b| vec![b]),
+            Encoding::WLATIN1 => {
+                let code = u32::from(c);
+                if code < 0x80 || (0xA0..=0xFF).contains(&code) {
+                    Some(vec![code as u8])
+                } else {
+                    WINDOWS_1252_HIGH
+                        .iter()
+                        .position(|high| high.eq(&Some(c)))
+                        .map(|n| vec![0x80 + n as u8])
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LineEnding {
+    /// keep line endings of template
+    #[default]
+    Keep,
+    LF,
+    CRLF,
+}
+
+/// a character not representable in target encoding, line and column start from 1
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EncodingIssue {
+    pub line: usize,
+    pub column: usize,
+    pub character: char,
+}
+
+/// how rendered programs are written to disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutputProfile {
+    pub encoding: Encoding,
+    /// write a BOM ahead of content, only applies to UTF-8
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+impl OutputProfile {
+    /// UTF-8 keeping line endings, BOM follows engine default
+    pub fn for_engine(engine: &Engine) -> OutputProfile {
+        OutputProfile {
+            bom: engine.bom(),
+            ..Default::default()
+        }
+    }
+
+    /// find characters not representable in target encoding
+    pub fn check(&self, content: &str) -> Vec<EncodingIssue> {
+        let mut issues = vec![];
+        for (n, line) in content.lines().enumerate() {
+            for (m, character) in line.chars().enumerate() {
+                if self.encoding.encode_char(character).is_none() {
+                    issues.push(EncodingIssue {
+                        line: n + 1,
+                        column: m + 1,
+                        character,
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    /// convert line endings and encode content, fails if any character is not representable
+    pub fn encode(&self, content: &str) -> anyhow::Result<Vec<u8>> {
+        let issues = self.check(content);
+        if !issues.is_empty() {
+            let detail = issues
+                .iter()
+                .take(MAX_REPORTED_ISSUES)
+                .map(|issue| {
+                    format!(
+                        "{:?} (U+{:04X}) at line {} column {}",
+                        issue.character,
+                        u32::from(issue.character),
+                        issue.line,
+                        issue.column
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            return Err(anyhow!(
+                "{} characters not representable in {:?}: {}",
+                issues.len(),
+                self.encoding,
+                detail
+            ));
+        }
+        let content = match self.line_ending {
+            LineEnding::Keep => content.to_string(),
+            LineEnding::LF => content.replace("\r\n", "\n"),
+            LineEnding::CRLF => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+        };
+        let mut data = vec![];
+        if self.bom && self.encoding.eq(&Encoding::UTF8) {
+            data.extend_from_slice(&BOM);
+        }
+        for c in content.chars() {
+            // checked above
+            data.extend(self.encoding.encode_char(c).unwrap_or_default());
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn encode_test() -> anyhow::Result<()> {
+        let profile = OutputProfile {
+            encoding: Encoding::UTF8,
+            bom: true,
+            line_ending: LineEnding::CRLF,
+        };
+        assert_eq!(
+            vec![239, 187, 191, b'a', b'\r', b'\n', b'b', b'\r', b'\n'],
+            profile.encode("a\nb\r\n")?
+        );
+        let profile = OutputProfile {
+            encoding: Encoding::LATIN1,
+            bom: true,
+            line_ending: LineEnding::LF,
+        };
+        assert_eq!(vec![b'e', 0xE9, b'\n'], profile.encode("eé\r\n")?);
+        let profile = OutputProfile {
+            encoding: Encoding::WLATIN1,
+            ..Default::default()
+        };
+        assert_eq!(vec![0x80, 0x93, 0x94], profile.encode("€“”")?);
+        Ok(())
+    }
+
+    #[test]
+    fn check_test() {
+        let latin1 = OutputProfile {
+            encoding: Encoding::LATIN1,
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                EncodingIssue {
+                    line: 2,
+                    column: 3,
+                    character: '€'
+                },
+                EncodingIssue {
+                    line: 2,
+                    column: 4,
+                    character: '研'
+                }
+            ],
+            latin1.check("ok\nab€研")
+        );
+        assert!(latin1.encode("研究").is_err());
+        let wlatin1 = OutputProfile {
+            encoding: Encoding::WLATIN1,
+            ..Default::default()
+        };
+        assert!(wlatin1.check("€").is_empty());
+        assert_eq!(1, wlatin1.check("\u{81}").len());
+        assert!(OutputProfile::default().check("研究").is_empty());
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tera::Context;
+
+use crate::{
+    clock::{Clock, DateFormat, SystemClock},
+    engine::{Comment, Engine},
+    generator::{FileOutcome, FileResult},
+    render::{OutputProfile, Render},
+    skeleton::{Builder, Skeleton, StatSkeleton},
+    template::VersionManager,
+};
+
+/// study level programs rendered once per purpose
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum SetupProgram {
+    Setup,
+    Autoexec,
+    Formats,
+}
+
+impl SetupProgram {
+    /// name of program, also the role of its template in `VersionManager::study`
+    pub fn name(&self) -> &'static str {
+        match self {
+            SetupProgram::Setup => "setup",
+            SetupProgram::Autoexec => "autoexec",
+            SetupProgram::Formats => "formats",
+        }
+    }
+}
+
+/// library assignment, path is relative to purpose folder and separated by `/`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LibraryRule {
+    pub name: String,
+    pub path: String,
+}
+
+impl LibraryRule {
+    fn new(name: &str, path: &str) -> LibraryRule {
+        LibraryRule {
+            name: name.into(),
+            path: path.into(),
+        }
+    }
+}
+
+/// libraries of raw, SDTM, ADaM, TFL output and their QC areas in standard stat skeleton
+pub fn default_library_rules() -> Vec<LibraryRule> {
+    vec![
+        LibraryRule::new("raw", "product/dataset/raw"),
+        LibraryRule::new("sdtm", "product/dataset/sdtm"),
+        LibraryRule::new("adam", "product/dataset/adam"),
+        LibraryRule::new("tfl", "product/output"),
+        LibraryRule::new("macros", "product/program/macros"),
+        LibraryRule::new("qcsdtm", "validation/dataset/sdtm"),
+        LibraryRule::new("qcadam", "validation/dataset/adam"),
+        LibraryRule::new("qctfl", "validation/output"),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Library {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetupTemplate {
+    pub program: SetupProgram,
+    pub template: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetupParam {
+    pub study: String,
+    pub engine: Engine,
+    pub developer: String,
+    pub templates: Vec<SetupTemplate>,
+    #[serde(default)]
+    pub date_format: DateFormat,
+    /// encoding, BOM and line ending of programs, engine default if none
+    #[serde(default)]
+    pub profile: Option<OutputProfile>,
+}
+
+/// context of setup templates, exposed as `item`
+#[derive(Debug, Serialize)]
+struct SetupItem<'a> {
+    name: &'a str,
+    study: &'a str,
+    engine: &'a str,
+    comment: Comment,
+    product: &'a str,
+    trial: &'a str,
+    purpose: &'a str,
+    start: String,
+    developer: &'a str,
+    libraries: &'a [Library],
+}
+
+pub struct SetupGenerator {
+    product_id: String,
+    trial_id: String,
+    purpose: String,
+    libraries: Vec<Library>,
+    template: Render,
+    clock: Box<dyn Clock>,
+}
+
+impl SetupGenerator {
+    /// ## create generator of study level programs
+    ///
+    /// ### Arguments
+    ///
+    /// @ builder: &Builder - builder the stat skeleton is built with
+    ///
+    /// @ skeleton: &StatSkeleton - only libraries whose folder is part of skeleton are assigned
+    ///
+    /// @ rules: &[LibraryRule] - library assignments, see `default_library_rules`
+    pub fn new(
+        builder: &Builder,
+        skeleton: &StatSkeleton,
+        rules: &[LibraryRule],
+    ) -> anyhow::Result<SetupGenerator> {
+        // library rule paths are relative to purpose folder, which is root of skeleton
+        let root = builder.resolve(&skeleton.root())?;
+        let dirs = skeleton
+            .paths()
+            .iter()
+            .map(|dir| Ok(builder.resolve(dir)?.strip_prefix(&root)?.to_path_buf()))
+            .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+        let purpose_dir = builder.directory().join(&root);
+        let libraries = rules
+            .iter()
+            .filter_map(|LibraryRule { name, path }| {
+                let library = components(path).into_iter().collect::<PathBuf>();
+                dirs.iter().find(|dir| dir.starts_with(&library))?;
+                Some(Library {
+                    name: name.into(),
+                    path: purpose_dir.join(&library).to_string_lossy().to_string(),
+                })
+            })
+            .collect();
+        Ok(SetupGenerator {
+            product_id: builder.product_id().into(),
+            trial_id: builder.trial_id().into(),
+            purpose: skeleton.purpose().into(),
+            libraries,
+            template: Render::new()?,
+            clock: Box::new(SystemClock),
+        })
+    }
+    /// render with template set of `VersionManager::study`
+    pub fn set_templates(&mut self, manager: &VersionManager) -> anyhow::Result<&mut Self> {
+        self.template = Render::with_templates(manager.template_set()?);
+        Ok(self)
+    }
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Box::new(clock);
+        self
+    }
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+    pub fn render(&self, dest: &Path, param: &SetupParam) -> anyhow::Result<Vec<FileResult>> {
+        let mut result = vec![];
+        if !dest.exists() {
+            fs::create_dir_all(dest)?;
+        }
+        let start = param.date_format.format(self.clock.today())?;
+        let profile = param
+            .profile
+            .clone()
+            .unwrap_or_else(|| OutputProfile::for_engine(&param.engine));
+        for SetupTemplate { program, template } in &param.templates {
+            let item = SetupItem {
+                name: program.name(),
+                study: &param.study,
+                engine: param.engine.name(),
+                comment: param.engine.comment(),
+                product: &self.product_id,
+                trial: &self.trial_id,
+                purpose: &self.purpose,
+                start: start.clone(),
+                developer: &param.developer,
+                libraries: &self.libraries,
+            };
+            let filename = format!("{}.{}", program.name(), param.engine.extension());
+            let existed = self
+                .template
+                .render(template, &item, &dest.join(&filename), &profile)?;
+            result.push(FileResult {
+                name: filename,
+                outcome: if existed {
+                    FileOutcome::Skipped
+                } else {
+                    FileOutcome::Created
+                },
+            });
+        }
+        Ok(result)
+    }
+}
+
+/// context of a made up setup program, used to lint study level templates
+pub(crate) fn sample_context(engine: &Engine) -> anyhow::Result<Context> {
+    let libraries = default_library_rules()
+        .into_iter()
+        .map(|LibraryRule { name, path }| Library { name, path })
+        .collect::<Vec<Library>>();
+    let mut ctx = Context::new();
+    ctx.insert(
+        "item",
+        &SetupItem {
+            name: SetupProgram::Setup.name(),
+            study: "STUDY",
+            engine: engine.name(),
+            comment: engine.comment(),
+            product: "product",
+            trial: "trial",
+            purpose: "csr",
+            start: "01JAN2024".into(),
+            developer: "developer",
+            libraries: &libraries,
+        },
+    );
+    Ok(ctx)
+}
+
+/// split library rule path into segments, rule paths may be separated by `\` or `/`
+fn components(path: &str) -> Vec<&str> {
+    path.split(['\\', '/'])
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn setup_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let skeleton = StatSkeleton::new(
+            "CSR",
+            br#"[
+                {"name": "product", "children": [
+                    {"name": "dataset", "children": [{"name": "sdtm"}, {"name": "adam"}]},
+                    {"name": "output"}
+                ]},
+                {"name": "validation", "children": [
+                    {"name": "dataset", "children": [{"name": "sdtm"}]}
+                ]}
+            ]"#,
+        )?;
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak112").set_trial_id("303");
+        let mut g = SetupGenerator::new(&builder, &skeleton, &default_library_rules())?;
+        g.set_clock(FixedClock(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()));
+        let names = g
+            .libraries()
+            .iter()
+            .map(|lib| lib.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(vec!["sdtm", "adam", "tfl", "qcsdtm"], names);
+
+        let dest = root.path().join("program");
+        let param = SetupParam {
+            study: "AK112-303".into(),
+            engine: Engine::SAS,
+            developer: "yuki".into(),
+            templates: vec![SetupTemplate {
+                program: SetupProgram::Setup,
+                template: "{{ item.product }}-{{ item.trial }} {{ item.purpose }} {{ item.start }}\n\
+                    {% for lib in item.libraries %}libname {{ lib.name }} \"{{ lib.path | safe }}\";\n{% endfor %}"
+                    .into(),
+            }],
+            date_format: DateFormat::DDMONYYYY,
+            profile: None,
+        };
+        let result = g.render(&dest, &param)?;
+        assert_eq!(FileOutcome::Created, result[0].outcome);
+        let content = fs::read_to_string(dest.join("setup.sas"))?;
+        let sdtm = root
+            .path()
+            .join("ak112")
+            .join("303")
+            .join("stats")
+            .join("CSR")
+            .join("product")
+            .join("dataset")
+            .join("sdtm");
+        assert!(content.starts_with("\u{feff}ak112-303 CSR 05MAR2024\n"));
+        assert!(content.contains(&format!("libname sdtm \"{}\";", sdtm.display())));
+        Ok(())
+    }
+
+    #[test]
+    fn custom_root_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let mut skeleton = StatSkeleton::new(
+            "CSR",
+            br#"[{"name": "product", "children": [
+                {"name": "dataset", "children": [{"name": "sdtm"}]}
+            ]}]"#,
+        )?;
+        skeleton.set_root("analysis/stats/{purpose}");
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak112").set_trial_id("303");
+        let g = SetupGenerator::new(&builder, &skeleton, &default_library_rules())?;
+        let sdtm = builder
+            .directory()
+            .join("analysis")
+            .join("stats")
+            .join("CSR")
+            .join("product")
+            .join("dataset")
+            .join("sdtm");
+        assert_eq!(1, g.libraries().len());
+        assert_eq!("sdtm", g.libraries()[0].name);
+        assert_eq!(sdtm.to_string_lossy(), g.libraries()[0].path);
+        Ok(())
+    }
+}
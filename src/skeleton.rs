@@ -1,9 +1,15 @@
 mod builder;
+mod custom;
+mod diff;
 mod documents;
+mod registry;
 mod skeleton;
 mod stat;
 
 pub use builder::Builder;
+pub use custom::CustomSkeleton;
+pub use diff::SkeletonDiff;
 pub use documents::DocumentSkeleton;
-pub use skeleton::STAT;
+pub use registry::SkeletonRegistry;
+pub use skeleton::{SeedFile, Skeleton, SkeletonFormat, SKELETON_SCHEMA, STAT};
 pub use stat::StatSkeleton;
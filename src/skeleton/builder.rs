@@ -1,76 +1,303 @@
 use std::{
-    cell::RefCell,
+    collections::HashMap,
     fs,
     path::{Path, PathBuf},
 };
 
-use super::skeleton::Skeleton;
+use tera::Context;
 
-#[derive(Debug, Default)]
+use super::{
+    registry::SkeletonRegistry,
+    skeleton::{interpolate, Skeleton},
+};
+use crate::render::{write, OutputProfile, Render};
+
+/// trial directory below root, unless changed with `set_layout`
+const DEFAULT_LAYOUT: &str = "{product}/{trial}";
+
+#[derive(Debug)]
 pub struct Builder {
     root: PathBuf,
-    product_id: RefCell<String>,
-    trial_id: RefCell<String>,
+    product_id: String,
+    trial_id: String,
+    delivery: String,
+    /// trial directory relative to root, with placeholders
+    layout: PathBuf,
 }
 
 impl Builder {
     pub fn new(root: &Path) -> Builder {
         Builder {
             root: root.to_path_buf(),
-            ..Default::default()
+            product_id: "".into(),
+            trial_id: "".into(),
+            delivery: "".into(),
+            layout: Path::new(DEFAULT_LAYOUT).iter().collect(),
         }
     }
-    pub fn set_product_id(&self, product_id: &str) -> &Self {
-        *self.product_id.borrow_mut() = product_id.into();
+    pub fn set_product_id(&mut self, product_id: &str) -> &mut Self {
+        self.product_id = product_id.into();
+        self
+    }
+    pub fn set_trial_id(&mut self, trial_id: &str) -> &mut Self {
+        self.trial_id = trial_id.into();
         self
     }
-    pub fn set_trial_id(&self, trial_id: &str) -> &Self {
-        *self.trial_id.borrow_mut() = trial_id.into();
+    /// delivery such as `dmc03`, resolves `{delivery}` placeholders of skeleton
+    pub fn set_delivery(&mut self, delivery: &str) -> &mut Self {
+        self.delivery = delivery.into();
         self
     }
-    pub fn build(&self, skeleton: impl Skeleton) -> anyhow::Result<()> {
-        let dirs = skeleton.paths();
+    /// ## layout of trial directory below root, `{product}/{trial}` by default
+    ///
+    /// only `{product}`, `{trial}` and `{delivery}` placeholders are allowed, e.g. `{product}/{product}-{trial}`
+    pub fn set_layout(&mut self, layout: &str) -> anyhow::Result<&mut Self> {
+        let layout = Path::new(layout).iter().collect::<PathBuf>();
+        let check = HashMap::from([
+            ("product", "product".to_string()),
+            ("trial", "trial".to_string()),
+            ("delivery", "delivery".to_string()),
+        ]);
+        interpolate(&layout, &check)?;
+        self.layout = layout;
+        Ok(self)
+    }
+    pub fn product_id(&self) -> &str {
+        &self.product_id
+    }
+    pub fn trial_id(&self) -> &str {
+        &self.trial_id
+    }
+    pub fn delivery(&self) -> &str {
+        &self.delivery
+    }
+    /// directory of trial, which skeleton paths are relative to
+    pub fn directory(&self) -> PathBuf {
+        self.layout.iter().fold(self.root.clone(), |dir, segment| {
+            dir.join(
+                segment
+                    .to_string_lossy()
+                    .replace("{product}", &self.product_id)
+                    .replace("{trial}", &self.trial_id)
+                    .replace("{delivery}", &self.delivery),
+            )
+        })
+    }
+    /// resolve `{product}`, `{trial}` and `{delivery}` placeholders of a skeleton path
+    pub fn resolve(&self, path: &Path) -> anyhow::Result<PathBuf> {
+        interpolate(path, &self.variables())
+    }
+    /// ## create directories of skeleton and seed their files, existing files are kept
+    ///
+    /// nothing is created if any placeholder can not be resolved.
+    /// Return directories created, existing ones are left out
+    pub fn build(&self, skeleton: &dyn Skeleton) -> anyhow::Result<Vec<PathBuf>> {
+        let trial = self.directory();
+        let created = self
+            .diff(skeleton)?
+            .to_create
+            .into_iter()
+            .map(|dir| trial.join(dir))
+            .collect::<Vec<PathBuf>>();
+        let dirs = skeleton
+            .paths()
+            .iter()
+            .map(|dir| self.resolve(dir))
+            .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+        let files = skeleton
+            .files()
+            .into_iter()
+            .map(|(path, file)| Ok((self.resolve(&path)?, file)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
         for dir in dirs {
-            let p = self
-                .root
-                .join(self.product_id.borrow().as_str())
-                .join(self.trial_id.borrow().as_str())
-                .join(dir);
-            fs::create_dir_all(p)?;
+            fs::create_dir_all(trial.join(dir))?;
         }
-        Ok(())
+        let render = Render::new()?;
+        let profile = OutputProfile::default();
+        for (path, file) in files {
+            let dest = trial.join(&path);
+            if dest.exists() {
+                continue;
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match &file.template {
+                Some(template) => {
+                    let mut ctx = Context::from_serialize(self.variables())?;
+                    ctx.insert(
+                        "directory",
+                        &dest.parent().unwrap_or(&dest).to_string_lossy(),
+                    );
+                    ctx.insert("path", &path.to_string_lossy());
+                    render.render_context(template, &ctx, &dest, &profile)?;
+                }
+                None => write(&dest, &file.content, &profile)?,
+            }
+        }
+        Ok(created)
+    }
+    /// ## build skeletons of registry by name in one call
+    ///
+    /// fails before creating anything if a name is not registered, return directories created
+    pub fn build_all(
+        &self,
+        registry: &SkeletonRegistry,
+        names: &[&str],
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let mut created = vec![];
+        for skeleton in registry.select(names)? {
+            created.append(&mut self.build(skeleton)?);
+        }
+        Ok(created)
+    }
+    fn variables(&self) -> HashMap<&str, String> {
+        HashMap::from([
+            ("product", self.product_id.clone()),
+            ("trial", self.trial_id.clone()),
+            ("delivery", self.delivery.clone()),
+        ])
     }
 }
 
 #[cfg(test)]
 mod skeleton_test {
-
-    use std::fs;
-
-    use crate::skeleton::{documents::DocumentSkeleton, stat::StatSkeleton};
+    use crate::skeleton::{
+        custom::CustomSkeleton, documents::DocumentSkeleton, stat::StatSkeleton,
+    };
 
     use super::*;
     #[test]
     fn test_generate_skeleton() {
-        let root = Path::new(r"D:\Studies");
-        let product_id = "ak105";
-        let trial_id = "302";
-        let purpose = "adhoc";
+        let root = tempfile::tempdir().unwrap();
+        let document_skeleton =
+            DocumentSkeleton::new(br#"[{"name": "specs"}, {"name": "protocol"}]"#).unwrap();
+        let stat_skeleton = StatSkeleton::new(
+            "CSR",
+            br#"[{"name": "product", "children": [
+                {"name": "dataset", "children": [{"name": "sdtm"}]},
+                {"name": "program", "children": [{"name": "macros"}]}
+            ]}]"#,
+        )
+        .unwrap();
+
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak101").set_trial_id("202");
+        builder.build(&document_skeleton).unwrap();
+        builder.build(&stat_skeleton).unwrap();
+        let trial = root.path().join("ak101").join("202");
+        assert!(trial.join("documents").join("specs").is_dir());
+        assert!(trial.join("documents").join("protocol").is_dir());
+        let product = trial.join("stats").join("CSR").join("product");
+        assert!(product.join("dataset").join("sdtm").is_dir());
+        assert!(product.join("program").join("macros").is_dir());
+        assert_eq!(2, fs::read_dir(&trial).unwrap().count());
+    }
+
+    #[test]
+    fn test_placeholders() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let template = br#"[
+            {"name": "{product}-{trial}", "children": [{"name": "{purpose}"}]},
+            {"name": "outputs", "children": [{"name": "{delivery}"}]}
+        ]"#;
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak101").set_trial_id("202");
+        let error = builder
+            .build(&DocumentSkeleton::new(template)?)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("{purpose}"));
+        assert!(!builder.directory().exists());
+
+        builder.set_delivery("dmc03");
+        builder.build(&StatSkeleton::new("CSR", template)?)?;
+        let csr = builder.directory().join("stats").join("CSR");
+        assert!(csr.join("ak101-202").join("CSR").is_dir());
+        assert!(csr.join("outputs").join("dmc03").is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn test_seed_files() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let template = br#"[
+            {"name": "product", "children": [
+                {"name": "program", "files": [
+                    {"name": "setup.sas", "template": "%let study={{ product }}-{{ trial }};"},
+                    {"name": "README.md", "content": "Production programs"}
+                ]},
+                {"name": "output", "files": [{"name": ".gitkeep"}]}
+            ]}
+        ]"#;
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak101").set_trial_id("202");
+        let program = builder
+            .directory()
+            .join("stats")
+            .join("CSR")
+            .join("product")
+            .join("program");
+        fs::create_dir_all(&program)?;
+        fs::write(program.join("README.md"), "edited")?;
+        builder.build(&StatSkeleton::new("CSR", template)?)?;
+        assert_eq!(
+            "%let study=ak101-202;",
+            fs::read_to_string(program.join("setup.sas"))?
+        );
+        assert_eq!("edited", fs::read_to_string(program.join("README.md"))?);
+        let gitkeep = program.parent().unwrap().join("output").join(".gitkeep");
+        assert_eq!("", fs::read_to_string(gitkeep)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_all() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let mut documents = DocumentSkeleton::new(br#"[{"name": "specs"}]"#)?;
+        documents.set_root("docs");
+        let mut registry = SkeletonRegistry::new();
+        registry
+            .register("documents", documents)
+            .register(
+                "stats",
+                StatSkeleton::new("CSR", br#"[{"name": "product"}]"#)?,
+            )
+            .register(
+                "dmc",
+                CustomSkeleton::new("dmc/{delivery}", br#"[{"name": "tables"}]"#)?,
+            );
+        assert_eq!(vec!["documents", "stats", "dmc"], registry.names());
 
-        let document_template =
-            fs::read(r"D:\projects\rusty\mobius_kit\.config\document_skeleton.json").unwrap();
-        let stat_template =
-            fs::read(r"D:\projects\rusty\mobius_kit\.config\stat_skeleton.json").unwrap();
-        let document_skeleton = DocumentSkeleton::new(&document_template).unwrap();
-        let stat_skeleton = StatSkeleton::new(purpose, &stat_template).unwrap();
+        let mut builder = Builder::new(root.path());
+        builder
+            .set_product_id("ak101")
+            .set_trial_id("202")
+            .set_delivery("dmc03")
+            .set_layout("{product}/{product}-{trial}")?;
+        let error = builder
+            .build_all(&registry, &["documents", "submission"])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("submission") && error.contains("documents, stats, dmc"));
+        assert!(!builder.directory().exists());
 
-        let builder = Builder::new(root);
-        builder.set_product_id(product_id).set_trial_id(trial_id);
-        builder.build(document_skeleton).unwrap();
-        builder.build(stat_skeleton).unwrap();
-        assert!(Path::new("D:\\Studies\\ak101\\202\\documents\\specs").exists());
-        assert!(Path::new("D:\\Studies\\ak101\\202\\documents\\protocol").exists());
-        assert!(Path::new("D:\\Studies\\ak101\\202\\stat\\CSR\\product\\dataset\\sdtm").exists());
-        assert!(Path::new("D:\\Studies\\ak101\\202\\stat\\CSR\\product\\program\\macros").exists());
+        let trial = root.path().join("ak101").join("ak101-202");
+        fs::create_dir_all(trial.join("docs"))?;
+        let created = builder.build_all(&registry, &["documents", "dmc"])?;
+        let dmc = trial.join("dmc");
+        assert_eq!(
+            vec![
+                trial.join("docs").join("specs"),
+                dmc.clone(),
+                dmc.join("dmc03"),
+                dmc.join("dmc03").join("tables"),
+            ],
+            created
+        );
+        assert!(!trial.join("stats").exists());
+        assert!(builder.build_all(&registry, &["dmc"])?.is_empty());
+        assert!(builder.set_layout("{product}/{study}").is_err());
+        Ok(())
     }
 }
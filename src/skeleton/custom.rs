@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+
+use super::skeleton::{parse, seed_files, walk, Facts, Node, SeedFile, Skeleton, SkeletonFormat};
+use crate::generator::StudyMetadata;
+
+/// user defined skeleton such as `dmc` or `submission`, created in its own root of trial directory
+pub struct CustomSkeleton {
+    root: PathBuf,
+    nodes: Vec<Node>,
+    facts: Facts,
+}
+
+impl CustomSkeleton {
+    pub fn new(root: &str, template: &[u8]) -> anyhow::Result<CustomSkeleton> {
+        CustomSkeleton::from_format(root, template, SkeletonFormat::Json)
+    }
+    /// ## read a JSON, YAML or TOML definition, see `SKELETON_SCHEMA`
+    ///
+    /// root may contain placeholders, such as `deliveries/{delivery}`
+    pub fn from_format(
+        root: &str,
+        template: &[u8],
+        format: SkeletonFormat,
+    ) -> anyhow::Result<CustomSkeleton> {
+        Ok(CustomSkeleton {
+            root: Path::new(root).iter().collect(),
+            nodes: parse(template, format)?,
+            facts: Facts::default(),
+        })
+    }
+    /// study metadata node conditions are checked against
+    pub fn set_study(&mut self, study: &StudyMetadata) -> &mut Self {
+        self.facts.set_study(study);
+        self
+    }
+}
+
+impl Skeleton for CustomSkeleton {
+    fn root(&self) -> PathBuf {
+        self.root.clone()
+    }
+    fn paths(&self) -> Vec<PathBuf> {
+        self.nodes
+            .iter()
+            .flat_map(|node| walk(&self.root, node, &self.facts))
+            .collect()
+    }
+    fn files(&self) -> Vec<(PathBuf, &SeedFile)> {
+        self.nodes
+            .iter()
+            .flat_map(|node| seed_files(&self.root, node, &self.facts))
+            .collect()
+    }
+}
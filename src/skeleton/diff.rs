@@ -0,0 +1,187 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::{builder::Builder, skeleton::Skeleton};
+
+/// ## directories of a skeleton compared with tree on disk, relative to trial directory
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct SkeletonDiff {
+    /// standard directories already existing
+    pub existing: Vec<PathBuf>,
+    /// standard directories `Builder::build` would create
+    pub to_create: Vec<PathBuf>,
+    /// existing directories not part of skeleton, only the topmost one of a non-standard tree is listed
+    pub non_standard: Vec<PathBuf>,
+}
+
+impl Builder {
+    /// ## dry run of `build`, nothing is created
+    ///
+    /// non-standard directories are searched below root of skeleton, such as `stats/CSR`,
+    /// so other purposes of a stat skeleton are not reported
+    pub fn diff(&self, skeleton: &dyn Skeleton) -> anyhow::Result<SkeletonDiff> {
+        let leaves = skeleton
+            .paths()
+            .iter()
+            .map(|dir| self.resolve(dir))
+            .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+        self.compare(skeleton, &leaves, &BTreeSet::new())
+    }
+
+    /// ## `diff` leaving out directories whose placeholders can not be resolved, such as `outputs/{delivery}`
+    ///
+    /// directories below their resolved parent are not reported non-standard.
+    /// Return diff and skeleton paths up to the first unresolved segment
+    pub(crate) fn diff_resolved(
+        &self,
+        skeleton: &dyn Skeleton,
+    ) -> anyhow::Result<(SkeletonDiff, Vec<PathBuf>)> {
+        let mut leaves = vec![];
+        let mut open = BTreeSet::new();
+        let mut unresolved = vec![];
+        for dir in skeleton.paths() {
+            if let Ok(leaf) = self.resolve(&dir) {
+                leaves.push(leaf);
+                continue;
+            }
+            let mut known = PathBuf::new();
+            let mut raw = PathBuf::new();
+            for segment in dir.iter() {
+                raw.push(segment);
+                match self.resolve(Path::new(segment)) {
+                    Ok(segment) => known.push(segment),
+                    Err(_) => break,
+                }
+            }
+            if !known.as_os_str().is_empty() {
+                leaves.push(known.clone());
+                open.insert(known);
+            }
+            if !unresolved.contains(&raw) {
+                unresolved.push(raw);
+            }
+        }
+        Ok((self.compare(skeleton, &leaves, &open)?, unresolved))
+    }
+
+    /// compare leaves with tree on disk, directories in open are not searched
+    fn compare(
+        &self,
+        skeleton: &dyn Skeleton,
+        leaves: &[PathBuf],
+        open: &BTreeSet<PathBuf>,
+    ) -> anyhow::Result<SkeletonDiff> {
+        let mut standard = BTreeSet::new();
+        for dir in leaves {
+            for ancestor in dir.ancestors() {
+                if ancestor.as_os_str().is_empty() {
+                    break;
+                }
+                standard.insert(ancestor.to_path_buf());
+            }
+        }
+        let trial = self.directory();
+        let mut diff = SkeletonDiff::default();
+        for dir in &standard {
+            if trial.join(dir).is_dir() {
+                diff.existing.push(dir.clone());
+            } else {
+                diff.to_create.push(dir.clone());
+            }
+        }
+        let root = self.resolve(&skeleton.root())?;
+        search(&trial, &root, &standard, open, &mut diff.non_standard)?;
+        Ok(diff)
+    }
+}
+
+fn search(
+    trial: &Path,
+    dir: &Path,
+    standard: &BTreeSet<PathBuf>,
+    open: &BTreeSet<PathBuf>,
+    result: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let path = trial.join(dir);
+    if !path.is_dir() {
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(&path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let child = dir.join(entry.file_name());
+        if open.contains(&child) {
+            continue;
+        }
+        if standard.contains(&child) {
+            search(trial, &child, standard, open, result)?;
+        } else {
+            result.push(child);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skeleton::stat::StatSkeleton;
+
+    #[test]
+    fn diff_test() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let mut builder = Builder::new(root.path());
+        builder.set_product_id("ak101").set_trial_id("202");
+        let skeleton = StatSkeleton::new(
+            "CSR",
+            br#"[{"name": "product", "children": [
+                {"name": "dataset", "children": [{"name": "sdtm"}, {"name": "adam"}]},
+                {"name": "output"}
+            ]}]"#,
+        )?;
+        let trial = builder.directory();
+        let csr = Path::new("stats").join("CSR");
+        fs::create_dir_all(
+            trial
+                .join(&csr)
+                .join("product")
+                .join("dataset")
+                .join("sdtm"),
+        )?;
+        fs::create_dir_all(trial.join(&csr).join("product").join("old").join("v1"))?;
+        fs::create_dir_all(trial.join("stats").join("adhoc"))?;
+        fs::create_dir_all(trial.join(&csr).join("tmp"))?;
+        fs::write(trial.join(&csr).join("product").join("notes.txt"), "")?;
+
+        let diff = builder.diff(&skeleton)?;
+        let product = csr.join("product");
+        assert_eq!(
+            vec![
+                PathBuf::from("stats"),
+                csr.clone(),
+                product.clone(),
+                product.join("dataset"),
+                product.join("dataset").join("sdtm"),
+            ],
+            diff.existing
+        );
+        assert_eq!(
+            vec![product.join("dataset").join("adam"), product.join("output")],
+            diff.to_create
+        );
+        assert_eq!(
+            vec![product.join("old"), csr.join("tmp")],
+            diff.non_standard
+        );
+        assert!(!trial.join(&product).join("output").exists());
+        Ok(())
+    }
+}
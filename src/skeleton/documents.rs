@@ -1,38 +1,81 @@
-use super::skeleton::{walk, Node, Skeleton, DOCUMENTS};
+use std::path::{Path, PathBuf};
+
+use super::skeleton::{
+    parse, seed_files, walk, Facts, Node, SeedFile, Skeleton, SkeletonFormat, DOCUMENTS,
+};
+use crate::generator::StudyMetadata;
 
 pub struct DocumentSkeleton {
+    root: PathBuf,
     nodes: Vec<Node>,
+    facts: Facts,
 }
 impl DocumentSkeleton {
     pub fn new(template: &[u8]) -> anyhow::Result<DocumentSkeleton> {
+        DocumentSkeleton::from_format(template, SkeletonFormat::Json)
+    }
+    /// read a JSON, YAML or TOML definition, see `SKELETON_SCHEMA`
+    pub fn from_format(
+        template: &[u8],
+        format: SkeletonFormat,
+    ) -> anyhow::Result<DocumentSkeleton> {
         Ok(DocumentSkeleton {
-            nodes: serde_json::from_slice(template)?,
+            root: Path::new(DOCUMENTS).into(),
+            nodes: parse(template, format)?,
+            facts: Facts::default(),
         })
     }
+    /// directory skeleton is created in, `documents` by default
+    pub fn set_root(&mut self, root: &str) -> &mut Self {
+        self.root = Path::new(root).iter().collect();
+        self
+    }
+    /// study metadata node conditions are checked against
+    pub fn set_study(&mut self, study: &StudyMetadata) -> &mut Self {
+        self.facts.set_study(study);
+        self
+    }
 }
 
 impl Skeleton for DocumentSkeleton {
-    fn paths(&self) -> Vec<String> {
+    fn root(&self) -> PathBuf {
+        self.root.clone()
+    }
+    fn paths(&self) -> Vec<PathBuf> {
         let mut dirs = vec![];
         self.nodes.iter().for_each(|node| {
-            walk(DOCUMENTS, node).into_iter().for_each(|dir| {
-                dirs.push(dir);
-            });
+            walk(&self.root, node, &self.facts)
+                .into_iter()
+                .for_each(|dir| {
+                    dirs.push(dir);
+                });
         });
         dirs
     }
+    fn files(&self) -> Vec<(PathBuf, &SeedFile)> {
+        self.nodes
+            .iter()
+            .flat_map(|node| seed_files(&self.root, node, &self.facts))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-
     use super::*;
     #[test]
     fn documents_test() {
-        let template =
-            fs::read(r"D:\projects\rusty\mobius_kit\.config\document_skeleton.json").unwrap();
-        let skeleton: Box<dyn Skeleton> = Box::new(DocumentSkeleton::new(&template).unwrap());
-        println!("{:?}", skeleton.paths());
+        let template = br#"[
+            {"name": "specs"},
+            {"name": "protocol", "children": [{"name": "amendments"}]}
+        ]"#;
+        let skeleton: Box<dyn Skeleton> = Box::new(DocumentSkeleton::new(template).unwrap());
+        assert_eq!(
+            vec![
+                Path::new(DOCUMENTS).join("specs"),
+                Path::new(DOCUMENTS).join("protocol").join("amendments")
+            ],
+            skeleton.paths()
+        );
     }
 }
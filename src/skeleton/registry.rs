@@ -0,0 +1,51 @@
+use anyhow::anyhow;
+
+use super::skeleton::Skeleton;
+
+/// ## named skeletons a `Builder` creates in any combination
+///
+/// such as `documents` and `stats`, plus user defined ones like `dmc` or `submission`
+#[derive(Default)]
+pub struct SkeletonRegistry {
+    skeletons: Vec<(String, Box<dyn Skeleton>)>,
+}
+
+impl SkeletonRegistry {
+    pub fn new() -> SkeletonRegistry {
+        SkeletonRegistry::default()
+    }
+    /// register skeleton under name, replacing one registered with the same name
+    pub fn register(&mut self, name: &str, skeleton: impl Skeleton + 'static) -> &mut Self {
+        self.skeletons.retain(|(registered, _)| registered != name);
+        self.skeletons.push((name.into(), Box::new(skeleton)));
+        self
+    }
+    pub fn get(&self, name: &str) -> Option<&dyn Skeleton> {
+        self.skeletons
+            .iter()
+            .find(|(registered, _)| registered == name)
+            .map(|(_, skeleton)| skeleton.as_ref())
+    }
+    /// names in order of registration
+    pub fn names(&self) -> Vec<&str> {
+        self.skeletons
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+    /// skeletons of names, fails on names not registered
+    pub(crate) fn select(&self, names: &[&str]) -> anyhow::Result<Vec<&dyn Skeleton>> {
+        names
+            .iter()
+            .map(|name| {
+                self.get(name).ok_or_else(|| {
+                    anyhow!(
+                        "Skeleton {} is not registered, available: {}",
+                        name,
+                        self.names().join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+}
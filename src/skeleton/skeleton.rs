@@ -1,33 +1,487 @@
-use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::generator::StudyMetadata;
 
 pub const DOCUMENTS: &str = "documents";
 pub const STAT: &str = "stats";
+/// JSON schema of skeleton definitions, for editors validating hand written skeletons
+pub const SKELETON_SCHEMA: &str = include_str!("../../schema/skeleton.schema.json");
+/// characters not allowed in folder or file names on Windows or Linux
+const ILLEGAL_CHARACTERS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+/// fields of `StudyMetadata` conditions may check, besides `standards.*`
+const STUDY_FIELDS: [&str; 3] = ["protocol_title", "phase", "sponsor"];
+const STANDARDS: &str = "standards.";
 
 pub trait Skeleton {
-    fn paths(&self) -> Vec<String>;
+    /// directory every path of skeleton is under, relative to trial directory
+    fn root(&self) -> PathBuf;
+    /// leaf directories relative to trial directory, joined by components so they fit every platform
+    fn paths(&self) -> Vec<PathBuf>;
+    /// files seeded into directories, paths are relative to trial directory
+    fn files(&self) -> Vec<(PathBuf, &SeedFile)>;
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Node {
     name: String,
     children: Option<Vec<Node>>,
+    #[serde(default)]
+    files: Vec<SeedFile>,
+    /// node and its descendants are left out unless condition holds
+    when: Option<Condition>,
+}
+
+/// ## condition of a node, every part given must hold
+///
+/// purposes match name or type ignoring case, such as `{"not_purpose": ["adhoc"]}`,
+/// study values match `StudyMetadata` ignoring case, such as `{"study": {"standards.SDTMIG": "3.3"}}`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    /// purposes node is created for
+    #[serde(default)]
+    purpose: Vec<String>,
+    /// purposes node is left out for
+    #[serde(default)]
+    not_purpose: Vec<String>,
+    /// study metadata required, `protocol_title`, `phase`, `sponsor` or `standards.<name>`
+    #[serde(default)]
+    study: HashMap<String, String>,
+}
+
+impl Condition {
+    fn holds(&self, facts: &Facts) -> bool {
+        let matches = |purposes: &[String]| {
+            purposes
+                .iter()
+                .any(|purpose| facts.purpose.contains(&purpose.to_lowercase()))
+        };
+        (self.purpose.is_empty() || matches(&self.purpose))
+            && !matches(&self.not_purpose)
+            && self.study.iter().all(|(field, value)| {
+                facts
+                    .study
+                    .get(&field.to_lowercase())
+                    .map(|actual| actual.eq_ignore_ascii_case(value))
+                    .unwrap_or(false)
+            })
+    }
 }
 
-pub fn walk(parent: &str, node: &Node) -> Vec<String> {
+/// ## what conditions of nodes are checked against
+///
+/// without a purpose, nodes limited to purposes are left out, such as in document skeletons
+#[derive(Debug, Default, Clone)]
+pub struct Facts {
+    /// name and type of purpose in lower case
+    purpose: Vec<String>,
+    /// study metadata by field in lower case, standards as `standards.<name>`
+    study: HashMap<String, String>,
+}
+
+impl Facts {
+    pub fn set_purpose(&mut self, name: &str, kind: &str) -> &mut Self {
+        self.purpose = vec![name.to_lowercase(), kind.to_lowercase()];
+        self
+    }
+    pub fn set_study(&mut self, study: &StudyMetadata) -> &mut Self {
+        self.study = [
+            ("protocol_title", &study.protocol_title),
+            ("phase", &study.phase),
+            ("sponsor", &study.sponsor),
+        ]
+        .into_iter()
+        .map(|(field, value)| (field.to_string(), value.clone()))
+        .chain(study.standards.iter().map(|(name, version)| {
+            (
+                format!("{}{}", STANDARDS, name.to_lowercase()),
+                version.clone(),
+            )
+        }))
+        .collect();
+        self
+    }
+    fn admits(&self, node: &Node) -> bool {
+        node.when
+            .as_ref()
+            .map(|condition| condition.holds(self))
+            .unwrap_or(true)
+    }
+}
+
+/// ## file created in a skeleton directory when built
+///
+/// such as an empty `.gitkeep`, a README describing purpose of folder or `setup.sas` rendered from a template,
+/// files already existing are never overwritten
+#[derive(Debug, Deserialize)]
+pub struct SeedFile {
+    pub name: String,
+    /// literal content
+    #[serde(default)]
+    pub content: String,
+    /// template rendered with `product`, `trial`, `directory` and `path`, used instead of content if given
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+/// leaf directories of node, nodes whose condition fails are left out with their descendants
+pub fn walk(parent: &Path, node: &Node, facts: &Facts) -> Vec<PathBuf> {
     let mut result = vec![];
-    let current_dir = if parent.is_empty() {
-        node.name.to_owned()
-    } else {
-        format!(r"{}\{}", parent, node.name)
-    };
+    if !facts.admits(node) {
+        return result;
+    }
+    let current_dir = parent.join(&node.name);
     match &node.children {
         Some(children) => {
             for child in children {
-                let dirs = walk(&current_dir, child);
+                let dirs = walk(&current_dir, child, facts);
                 dirs.into_iter().for_each(|dir| result.push(dir));
             }
+            // every child left out, node itself is the leaf
+            if result.is_empty() {
+                result.push(current_dir);
+            }
         }
         None => result.push(current_dir),
     }
     result
 }
+
+/// format of skeleton definition
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SkeletonFormat {
+    /// list of nodes
+    #[default]
+    Json,
+    /// list of nodes
+    Yaml,
+    /// nodes as `[[nodes]]` tables, children as `[[nodes.children]]`
+    Toml,
+}
+
+impl SkeletonFormat {
+    /// format by extension, `json`, `yaml`, `yml` or `toml`
+    pub fn from_path(path: &Path) -> anyhow::Result<SkeletonFormat> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "json" => Ok(SkeletonFormat::Json),
+            "yaml" | "yml" => Ok(SkeletonFormat::Yaml),
+            "toml" => Ok(SkeletonFormat::Toml),
+            _ => Err(anyhow!(
+                "Unknown skeleton format of {}, expect json, yaml or toml",
+                path.display()
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TomlDefinition {
+    nodes: Vec<Node>,
+}
+
+/// ## read nodes of a skeleton definition and validate them
+///
+/// sibling names must be unique ignoring case, names must not be empty, `.` or `..`,
+/// contain characters illegal in paths or end with a dot or space.
+/// Every violation is reported with path of node, such as `product/dataset/sd:tm`
+pub fn parse(template: &[u8], format: SkeletonFormat) -> anyhow::Result<Vec<Node>> {
+    let nodes = match format {
+        SkeletonFormat::Json => serde_json::from_slice(template)?,
+        SkeletonFormat::Yaml => serde_yaml::from_slice(template)?,
+        SkeletonFormat::Toml => {
+            toml::from_str::<TomlDefinition>(std::str::from_utf8(template)?)?.nodes
+        }
+    };
+    let mut errors = vec![];
+    validate(Path::new(""), &nodes, &mut errors);
+    if !errors.is_empty() {
+        return Err(anyhow!(
+            "Invalid skeleton definition:\n{}",
+            errors.join("\n")
+        ));
+    }
+    Ok(nodes)
+}
+
+fn validate(parent: &Path, nodes: &[Node], errors: &mut Vec<String>) {
+    let mut names = HashSet::new();
+    for node in nodes {
+        check(parent, &node.name, "folder", &mut names, errors);
+        let current = parent.join(&node.name);
+        for field in node
+            .when
+            .iter()
+            .flat_map(|condition| condition.study.keys())
+        {
+            let field = field.to_lowercase();
+            if !STUDY_FIELDS.contains(&field.as_str()) && !field.starts_with(STANDARDS) {
+                errors.push(format!(
+                    "`{}`: unknown study field `{}` in condition",
+                    display(parent, &node.name),
+                    field
+                ));
+            }
+        }
+        // files share directory with child folders
+        let mut entries = node
+            .children
+            .iter()
+            .flatten()
+            .map(|child| child.name.to_lowercase())
+            .collect::<HashSet<String>>();
+        for file in &node.files {
+            check(&current, &file.name, "file", &mut entries, errors);
+        }
+        if let Some(children) = &node.children {
+            validate(&current, children, errors);
+        }
+    }
+}
+
+fn check(
+    parent: &Path,
+    name: &str,
+    kind: &str,
+    names: &mut HashSet<String>,
+    errors: &mut Vec<String>,
+) {
+    let path = display(parent, name);
+    if let Some(problem) = name_problem(name) {
+        errors.push(format!("`{}`: {} name {}", path, kind, problem));
+    } else if !names.insert(name.to_lowercase()) {
+        errors.push(format!("`{}`: duplicate name among siblings", path));
+    }
+}
+
+fn name_problem(name: &str) -> Option<String> {
+    if name.trim().is_empty() {
+        return Some("is empty".into());
+    }
+    if name == "." || name == ".." {
+        return Some(format!("`{}` is not allowed", name));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| ILLEGAL_CHARACTERS.contains(c) || c.is_control())
+    {
+        return Some(format!("contains illegal character {:?}", c));
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Some("ends with a dot or space".into());
+    }
+    None
+}
+
+/// node path separated by `/` on every platform
+fn display(parent: &Path, name: &str) -> String {
+    parent
+        .iter()
+        .map(|segment| segment.to_string_lossy().to_string())
+        .chain([name.to_string()])
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// ## replace placeholders such as `{product}` in every segment of path
+///
+/// fails on placeholders not in variables or without value, so no directory is named after a placeholder
+pub fn interpolate(path: &Path, variables: &HashMap<&str, String>) -> anyhow::Result<PathBuf> {
+    let pattern = Regex::new(r"\{([^{}]*)\}")?;
+    let mut result = PathBuf::new();
+    for segment in path.iter() {
+        let segment = segment.to_string_lossy();
+        let mut error = None;
+        let resolved = pattern.replace_all(&segment, |captures: &regex::Captures| match variables
+            .get(&captures[1])
+            .filter(|value| !value.is_empty())
+        {
+            Some(value) => value.to_owned(),
+            None => {
+                error.get_or_insert(captures[0].to_string());
+                captures[0].to_string()
+            }
+        });
+        if let Some(placeholder) = error {
+            return Err(anyhow!(
+                "Unknown or empty placeholder {} in {}",
+                placeholder,
+                path.display()
+            ));
+        }
+        result.push(resolved.as_ref());
+    }
+    Ok(result)
+}
+
+/// seed files of node and its descendants, nodes whose condition fails are left out
+pub fn seed_files<'a>(
+    parent: &Path,
+    node: &'a Node,
+    facts: &Facts,
+) -> Vec<(PathBuf, &'a SeedFile)> {
+    if !facts.admits(node) {
+        return vec![];
+    }
+    let current_dir = parent.join(&node.name);
+    let mut result = node
+        .files
+        .iter()
+        .map(|file| (current_dir.join(&file.name), file))
+        .collect::<Vec<(PathBuf, &SeedFile)>>();
+    for child in node.children.iter().flatten() {
+        result.append(&mut seed_files(&current_dir, child, facts));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn walk_test() {
+        let node: Node = serde_json::from_str(
+            r#"{"name": "product", "children": [
+                {"name": "dataset", "children": [{"name": "sdtm"}]},
+                {"name": "output"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                Path::new(STAT).join("product").join("dataset").join("sdtm"),
+                Path::new(STAT).join("product").join("output")
+            ],
+            walk(Path::new(STAT), &node, &Facts::default())
+        );
+    }
+
+    #[test]
+    fn parse_test() {
+        let yaml = br#"
+- name: product
+  children:
+    - name: dataset
+      files:
+        - name: .gitkeep
+- name: validation
+"#;
+        let toml = br#"
+[[nodes]]
+name = "product"
+
+[[nodes.children]]
+name = "dataset"
+files = [{ name = ".gitkeep" }]
+
+[[nodes]]
+name = "validation"
+"#;
+        let json = br#"[
+            {"name": "product", "children": [{"name": "dataset", "files": [{"name": ".gitkeep"}]}]},
+            {"name": "validation"}
+        ]"#;
+        for (template, format) in [
+            (&yaml[..], SkeletonFormat::Yaml),
+            (&toml[..], SkeletonFormat::Toml),
+            (&json[..], SkeletonFormat::Json),
+        ] {
+            let nodes = parse(template, format).unwrap();
+            let paths = nodes
+                .iter()
+                .flat_map(|node| walk(Path::new(""), node, &Facts::default()))
+                .collect::<Vec<PathBuf>>();
+            assert_eq!(
+                vec![
+                    Path::new("product").join("dataset"),
+                    PathBuf::from("validation")
+                ],
+                paths
+            );
+            assert_eq!(
+                1,
+                seed_files(Path::new(""), &nodes[0], &Facts::default()).len()
+            );
+        }
+        assert_eq!(
+            SkeletonFormat::Yaml,
+            SkeletonFormat::from_path(Path::new("stat.yml")).unwrap()
+        );
+        let schema: serde_json::Value = serde_json::from_str(SKELETON_SCHEMA).unwrap();
+        assert_eq!("Skeleton", schema["title"]);
+    }
+
+    #[test]
+    fn validate_test() {
+        let yaml = br#"
+- name: product
+  children:
+    - name: Dataset
+    - name: dataset
+    - name: "sd:tm"
+    - name: "out."
+      files:
+        - name: README.md
+        - name: readme.md
+- name: "  "
+"#;
+        let error = parse(yaml, SkeletonFormat::Yaml).unwrap_err().to_string();
+        assert!(error.contains("`product/dataset`: duplicate name among siblings"));
+        assert!(error.contains("`product/sd:tm`: folder name contains illegal character ':'"));
+        assert!(error.contains("`product/out.`: folder name ends with a dot or space"));
+        assert!(error.contains("`product/out./readme.md`: duplicate name"));
+        assert!(error.contains("`  `: folder name is empty"));
+        assert!(!error.contains("`product/Dataset`"));
+    }
+
+    #[test]
+    fn interpolate_test() {
+        let variables = HashMap::from([
+            ("product", "ak112".to_string()),
+            ("delivery", "".to_string()),
+        ]);
+        assert_eq!(
+            Path::new("ak112-docs").join("specs"),
+            interpolate(&Path::new("{product}-docs").join("specs"), &variables).unwrap()
+        );
+        let error = interpolate(&Path::new("outputs").join("{delivery}"), &variables)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("{delivery}"));
+        assert!(interpolate(Path::new("{protocol}"), &variables).is_err());
+    }
+
+    #[test]
+    fn seed_files_test() {
+        let node: Node = serde_json::from_str(
+            r#"{"name": "program", "files": [{"name": "setup.sas", "template": "{{ trial }}"}],
+                "children": [{"name": "macros", "files": [{"name": ".gitkeep"}]}]}"#,
+        )
+        .unwrap();
+        let files = seed_files(Path::new(STAT), &node, &Facts::default());
+        assert_eq!(2, files.len());
+        assert_eq!(
+            Path::new(STAT).join("program").join("setup.sas"),
+            files[0].0
+        );
+        assert_eq!(Some("{{ trial }}".into()), files[0].1.template);
+        assert_eq!(
+            Path::new(STAT)
+                .join("program")
+                .join("macros")
+                .join(".gitkeep"),
+            files[1].0
+        );
+        assert!(files[1].1.content.is_empty());
+    }
+}
@@ -1,42 +1,161 @@
-use super::skeleton::{walk, Node, Skeleton, STAT};
+use std::path::{Path, PathBuf};
+
+use super::skeleton::{
+    parse, seed_files, walk, Facts, Node, SeedFile, Skeleton, SkeletonFormat, STAT,
+};
+use crate::generator::StudyMetadata;
 
 pub struct StatSkeleton {
     purpose: String,
+    /// may contain `{purpose}`
+    root: PathBuf,
     nodes: Vec<Node>,
+    facts: Facts,
 }
 impl StatSkeleton {
     pub fn new(purpose: &str, template: &[u8]) -> anyhow::Result<StatSkeleton> {
+        StatSkeleton::from_format(purpose, template, SkeletonFormat::Json)
+    }
+    /// read a JSON, YAML or TOML definition, see `SKELETON_SCHEMA`
+    pub fn from_format(
+        purpose: &str,
+        template: &[u8],
+        format: SkeletonFormat,
+    ) -> anyhow::Result<StatSkeleton> {
+        let mut facts = Facts::default();
+        facts.set_purpose(purpose, purpose);
         Ok(StatSkeleton {
             purpose: purpose.into(),
-            nodes: serde_json::from_slice(template)?,
+            root: Path::new(STAT).join("{purpose}"),
+            nodes: parse(template, format)?,
+            facts,
         })
     }
+    /// directory skeleton is created in, `stats/{purpose}` by default
+    pub fn set_root(&mut self, root: &str) -> &mut Self {
+        self.root = Path::new(root).iter().collect();
+        self
+    }
+    /// type of purpose node conditions match besides its name, such as `dmc` for `DMC03`
+    pub fn set_purpose_type(&mut self, kind: &str) -> &mut Self {
+        self.facts.set_purpose(&self.purpose, kind);
+        self
+    }
+    /// study metadata node conditions are checked against
+    pub fn set_study(&mut self, study: &StudyMetadata) -> &mut Self {
+        self.facts.set_study(study);
+        self
+    }
+    pub fn purpose(&self) -> &str {
+        &self.purpose
+    }
+    /// resolve `{purpose}` placeholders, others are left to `Builder`
+    fn fill(&self, path: PathBuf) -> PathBuf {
+        path.iter()
+            .map(|segment| {
+                segment
+                    .to_string_lossy()
+                    .replace("{purpose}", &self.purpose)
+            })
+            .collect()
+    }
 }
 
 impl Skeleton for StatSkeleton {
-    fn paths(&self) -> Vec<String> {
+    fn root(&self) -> PathBuf {
+        self.fill(self.root.clone())
+    }
+    fn paths(&self) -> Vec<PathBuf> {
         let mut dirs = vec![];
         self.nodes.iter().for_each(|node| {
-            walk(&format!(r"{}\{}", STAT, self.purpose), node)
+            walk(&self.root, node, &self.facts)
                 .into_iter()
                 .for_each(|dir| {
-                    dirs.push(dir);
+                    dirs.push(self.fill(dir));
                 });
         });
         dirs
     }
+    fn files(&self) -> Vec<(PathBuf, &SeedFile)> {
+        self.nodes
+            .iter()
+            .flat_map(|node| seed_files(&self.root, node, &self.facts))
+            .map(|(path, file)| (self.fill(path), file))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-
     use super::*;
     #[test]
     fn stat_test() {
-        let template =
-            fs::read(r"D:\projects\rusty\mobius_kit\.config\stat_skeleton.json").unwrap();
-        let skeleton: Box<dyn Skeleton> = Box::new(StatSkeleton::new("CSR", &template).unwrap());
-        println!("{:?}", skeleton.paths());
+        let template = br#"[
+            {"name": "product", "children": [{"name": "dataset", "children": [{"name": "sdtm"}]}]},
+            {"name": "validation", "children": [{"name": "{purpose}-qc"}]}
+        ]"#;
+        let skeleton: Box<dyn Skeleton> = Box::new(StatSkeleton::new("CSR", template).unwrap());
+        let csr = Path::new(STAT).join("CSR");
+        assert_eq!(
+            vec![
+                csr.join("product").join("dataset").join("sdtm"),
+                csr.join("validation").join("CSR-qc")
+            ],
+            skeleton.paths()
+        );
+    }
+
+    #[test]
+    fn conditional_test() -> anyhow::Result<()> {
+        let template = br#"[
+            {"name": "product", "children": [
+                {"name": "dataset", "children": [
+                    {"name": "sdtm", "when": {"not_purpose": ["adhoc"]}},
+                    {"name": "adam"}
+                ]},
+                {"name": "unblinded", "when": {"purpose": ["dmc"]},
+                 "files": [{"name": "README.md"}]},
+                {"name": "pk", "when": {"study": {"phase": "1", "standards.ADaMIG": "1.3"}}}
+            ]}
+        ]"#;
+        let dataset = |purpose: &str| {
+            Path::new(STAT)
+                .join(purpose)
+                .join("product")
+                .join("dataset")
+        };
+        let adhoc = StatSkeleton::new("adhoc", template)?;
+        assert_eq!(vec![dataset("adhoc").join("adam")], adhoc.paths());
+
+        let mut dmc = StatSkeleton::new("DMC03", template)?;
+        dmc.set_purpose_type("DMC");
+        let study = StudyMetadata {
+            phase: "1".into(),
+            standards: [("ADaMIG".to_string(), "1.3".to_string())].into(),
+            ..Default::default()
+        };
+        dmc.set_study(&study);
+        let product = Path::new(STAT).join("DMC03").join("product");
+        assert_eq!(
+            vec![
+                dataset("DMC03").join("sdtm"),
+                dataset("DMC03").join("adam"),
+                product.join("unblinded"),
+                product.join("pk"),
+            ],
+            dmc.paths()
+        );
+        assert_eq!(1, dmc.files().len());
+        assert!(StatSkeleton::new("CSR", template)?.files().is_empty());
+
+        let error = StatSkeleton::new(
+            "CSR",
+            br#"[{"name": "pk", "when": {"study": {"indication": "NSCLC"}}}]"#,
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(error.contains("`pk`: unknown study field `indication` in condition"));
+        Ok(())
     }
 }
@@ -1,3 +1,5 @@
+pub(crate) mod lint;
 mod version;
 
+pub use lint::{LintIssue, LintKind, LintOption, LintReport};
 pub use version::{Version, VersionManager, VersionManagerParam};
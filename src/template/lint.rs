@@ -0,0 +1,320 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tera::{
+    ast::{Expr, ExprVal, Node},
+    Context, Template,
+};
+
+use crate::render::Render;
+
+/// variables whose members are user defined, so members are not checked
+const OPEN_SCOPES: [&str; 2] = ["vars", "study.standards"];
+/// variables tera defines while rendering
+const TERA_VARIABLES: [&str; 2] = ["loop", "__tera_context"];
+/// tests used to guard optional variables
+const DEFINED_TESTS: [&str; 2] = ["defined", "undefined"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintOption {
+    /// text rendered programs must contain, such as `Program Name` of company header, ignoring case
+    pub required_sections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LintKind {
+    Parse,
+    UnknownVariable,
+    MissingSection,
+    Render,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LintIssue {
+    pub kind: LintKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintReport {
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+    fn push(&mut self, kind: LintKind, message: String) {
+        self.issues.push(LintIssue { kind, message });
+    }
+}
+
+/// ## lint a template
+///
+/// ### Arguments
+///
+/// @ sample: &Context - context of a sample item, variables referenced by template are checked against it
+pub fn lint(render: &Render, template: &str, sample: &Context, option: &LintOption) -> LintReport {
+    let mut report = LintReport::default();
+    let ast = match Template::new("template", None, template) {
+        Ok(template) => template.ast,
+        Err(e) => {
+            report.push(LintKind::Parse, error_message(&e));
+            return report;
+        }
+    };
+
+    let mut variables = Variables::default();
+    variables.nodes(&ast);
+    let mut sample = sample.clone().into_json();
+    for ident in &variables.idents {
+        let path = segments(ident);
+        if variables.locals.contains(path[0])
+            || variables.guarded.contains(ident)
+            || TERA_VARIABLES.contains(&path[0])
+        {
+            continue;
+        }
+        if let Some(scope) = OPEN_SCOPES
+            .iter()
+            .find(|scope| ident.starts_with(&format!("{}.", scope)))
+        {
+            // user defined members are unknown until rendering, fill them to render sample
+            placeholder(&mut sample, &path, segments(scope).len());
+            continue;
+        }
+        if !resolve(&sample, &path) {
+            report.push(
+                LintKind::UnknownVariable,
+                format!("Variable `{}` is not part of template context", ident),
+            );
+        }
+    }
+
+    let rendered = Context::from_value(sample)
+        .map_err(|e| error_message(&e))
+        .and_then(|ctx| {
+            render
+                .render_str(template, &ctx)
+                .map_err(|e| format!("{:#}", e))
+        });
+    match rendered {
+        Ok(rendered) => {
+            let rendered = rendered.to_lowercase();
+            for section in &option.required_sections {
+                if !rendered.contains(&section.to_lowercase()) {
+                    report.push(
+                        LintKind::MissingSection,
+                        format!("Required section `{}` is missing", section),
+                    );
+                }
+            }
+        }
+        Err(e) => report.push(LintKind::Render, e),
+    }
+    report
+}
+
+fn error_message(e: &tera::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(e);
+    while let Some(e) = source {
+        message = format!("{}: {}", message, e);
+        source = e.source();
+    }
+    message
+}
+
+/// split ident such as `item.slot[0]` into `item` and `slot`
+fn segments(ident: &str) -> Vec<&str> {
+    ident
+        .split('.')
+        .map(|segment| segment.split('[').next().unwrap_or(segment))
+        .collect()
+}
+
+/// whether every object member along path exists, members of non objects are not checked
+fn resolve(sample: &Value, path: &[&str]) -> bool {
+    let mut current = sample;
+    for segment in path {
+        match current {
+            Value::Object(map) => match map.get(*segment) {
+                Some(value) => current = value,
+                None => return false,
+            },
+            _ => return true,
+        }
+    }
+    true
+}
+
+/// insert an empty string at path, creating objects from depth on
+fn placeholder(sample: &mut Value, path: &[&str], depth: usize) {
+    let mut current = sample;
+    for (n, segment) in path.iter().enumerate() {
+        let last = n + 1 == path.len();
+        let Value::Object(map) = current else {
+            return;
+        };
+        if n < depth && !map.contains_key(*segment) {
+            return;
+        }
+        current = map.entry(segment.to_string()).or_insert_with(|| {
+            if last {
+                Value::String("".into())
+            } else {
+                Value::Object(Default::default())
+            }
+        });
+    }
+}
+
+/// variables a template references and names it defines itself
+#[derive(Default)]
+struct Variables {
+    idents: Vec<String>,
+    locals: HashSet<String>,
+    /// variables tested with `is defined`, which may be absent
+    guarded: HashSet<String>,
+}
+
+impl Variables {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::VariableBlock(_, expr) => self.expr(expr),
+                Node::MacroDefinition(_, definition, _) => {
+                    for (arg, default) in &definition.args {
+                        self.locals.insert(arg.into());
+                        if let Some(default) = default {
+                            self.expr(default);
+                        }
+                    }
+                    self.nodes(&definition.body);
+                }
+                Node::Set(_, set) => {
+                    self.locals.insert(set.key.to_owned());
+                    self.expr(&set.value);
+                }
+                Node::FilterSection(_, section, _) => {
+                    section.filter.args.values().for_each(|arg| self.expr(arg));
+                    self.nodes(&section.body);
+                }
+                Node::Block(_, block, _) => self.nodes(&block.body),
+                Node::Forloop(_, forloop, _) => {
+                    if let Some(key) = &forloop.key {
+                        self.locals.insert(key.to_owned());
+                    }
+                    self.locals.insert(forloop.value.to_owned());
+                    self.expr(&forloop.container);
+                    self.nodes(&forloop.body);
+                    if let Some(body) = &forloop.empty_body {
+                        self.nodes(body);
+                    }
+                }
+                Node::If(condition, _) => {
+                    for (_, expr, body) in &condition.conditions {
+                        self.expr(expr);
+                        self.nodes(body);
+                    }
+                    if let Some((_, body)) = &condition.otherwise {
+                        self.nodes(body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        self.value(&expr.val);
+        for filter in &expr.filters {
+            filter.args.values().for_each(|arg| self.expr(arg));
+        }
+    }
+
+    fn value(&mut self, value: &ExprVal) {
+        match value {
+            ExprVal::Ident(ident) => self.idents.push(ident.to_owned()),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            }
+            ExprVal::Test(test) => {
+                if DEFINED_TESTS.contains(&test.name.as_str()) {
+                    self.guarded.insert(test.ident.to_owned());
+                } else {
+                    self.idents.push(test.ident.to_owned());
+                }
+                test.args.iter().for_each(|arg| self.expr(arg));
+            }
+            ExprVal::MacroCall(call) => call.args.values().for_each(|arg| self.expr(arg)),
+            ExprVal::FunctionCall(call) => call.args.values().for_each(|arg| self.expr(arg)),
+            ExprVal::Array(values) => values.iter().for_each(|value| self.expr(value)),
+            ExprVal::StringConcat(concat) => {
+                concat.values.iter().for_each(|value| self.value(value))
+            }
+            ExprVal::In(within) => {
+                self.expr(&within.lhs);
+                self.expr(&within.rhs);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Context {
+        let mut ctx = Context::new();
+        ctx.insert(
+            "item",
+            &serde_json::json!({"name": "ae", "slot": ["%checklog"], "output": {"kind": "table"}}),
+        );
+        ctx.insert("study", &serde_json::json!({"standards": {}}));
+        ctx.insert("vars", &serde_json::json!({}));
+        ctx
+    }
+
+    fn kinds(report: &LintReport) -> Vec<LintKind> {
+        report.issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn lint_test() -> anyhow::Result<()> {
+        let render = Render::new()?;
+        let option = LintOption {
+            required_sections: vec!["Program Name".into()],
+        };
+        let report = lint(
+            &render,
+            "/* Program Name: {{ item.name }} {{ item.output.kind }} {{ vars.reviewer }} \
+            {{ study.standards.SDTMIG }} */\n{% for s in item.slot %}{{ s }}{{ loop.index }}{% endfor %}\
+            {% if item.missing is defined %}{{ item.missing }}{% endif %}",
+            &sample(),
+            &option,
+        );
+        assert_eq!(Vec::<LintKind>::new(), kinds(&report));
+        assert!(report.passed());
+
+        let report = lint(&render, "{{ item.nmae }}", &sample(), &option);
+        assert_eq!(
+            vec![LintKind::UnknownVariable, LintKind::Render],
+            kinds(&report)
+        );
+
+        let report = lint(&render, "{% if item.name %}", &sample(), &option);
+        assert_eq!(vec![LintKind::Parse], kinds(&report));
+
+        let report = lint(&render, "{{ item.name }}", &sample(), &option);
+        assert_eq!(vec![LintKind::MissingSection], kinds(&report));
+        Ok(())
+    }
+}
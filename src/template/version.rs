@@ -4,15 +4,17 @@ use std::{
 };
 
 use serde::{Deserialize, Serialize};
+use tera::Tera;
 
-use crate::Kind;
+use crate::{generator, render::Render, setup, Engine, Kind, LintOption, LintReport};
 
 const SDTM_TEMPLATE: &str = "sdtm";
 const ADAM_TEMPLATE: &str = "adam";
 const TFL_TEMPLATE: &str = "tfls";
-const TEMPLATE_EXTENTION: &str = ".sas";
+const STUDY_TEMPLATE: &str = "study";
+const SHARED_TEMPLATE: &str = "shared";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Version {
     pub name: String,
     pub role: String,
@@ -23,12 +25,16 @@ pub struct VersionManagerParam<'a> {
     pub offical_path: &'a Path,
     pub private_path: &'a Path,
     pub kind: Kind,
+    pub engine: Engine,
 }
 
 pub struct VersionManager {
     offical_path: PathBuf,
     private_path: PathBuf,
-    kind: Kind,
+    /// none for study level templates, such as setup and autoexec
+    kind: Option<Kind>,
+    engine: Engine,
+    lint_option: LintOption,
 }
 
 impl VersionManager {
@@ -37,14 +43,50 @@ impl VersionManager {
             offical_path,
             private_path,
             kind,
+            engine,
         } = param;
         VersionManager {
             offical_path: offical_path.into(),
             private_path: private_path.into(),
-            kind: kind.to_owned(),
+            kind: Some(kind.to_owned()),
+            engine: *engine,
+            lint_option: LintOption::default(),
         }
     }
 
+    /// manager of study level templates, whose role is the program name, such as `setup.v1.sas`
+    pub fn study(offical_path: &Path, private_path: &Path, engine: Engine) -> VersionManager {
+        VersionManager {
+            offical_path: offical_path.into(),
+            private_path: private_path.into(),
+            kind: None,
+            engine,
+            lint_option: LintOption::default(),
+        }
+    }
+
+    /// options offical templates are linted with before saved
+    pub fn set_lint_option(&mut self, option: LintOption) -> &mut Self {
+        self.lint_option = option;
+        self
+    }
+
+    /// ## lint a template of version against template set and a sample item of kind
+    ///
+    /// template should parse, reference only known variables, contain required sections
+    /// and render a sample item, variables under `vars` and `study.standards` are user defined and not checked.
+    /// Sample item is a qc item for role `qc` and a dev item otherwise
+    pub fn lint_template(&self, version: &Version, bytes: &[u8]) -> anyhow::Result<LintReport> {
+        let template = String::from_utf8_lossy(bytes);
+        let template = template.trim_start_matches('\u{feff}');
+        let sample = match &self.kind {
+            Some(kind) => generator::sample_context(kind, &self.engine, version)?,
+            None => setup::sample_context(&self.engine)?,
+        };
+        let render = Render::with_templates(self.template_set()?);
+        Ok(render.lint(template, &sample, &self.lint_option))
+    }
+
     pub fn list_templates(&self) -> anyhow::Result<Vec<Version>> {
         let mut templates = vec![];
         let mut offical = self.get_offical_template()?;
@@ -61,35 +103,110 @@ impl VersionManager {
             offical,
         } = version;
         let base_directory = self.base_directory(*offical);
-        let filename = format!("{}.{}{}", role, name, TEMPLATE_EXTENTION);
+        let filename = format!("{}.{}.{}", role, name, self.engine.extension());
         let filepath = base_directory.join(filename);
         Ok(fs::read(&filepath)?)
     }
 
+    /// offical templates failing lint are refused
     pub fn save_template(&self, version: &Version, bytes: &[u8]) -> anyhow::Result<()> {
         let Version {
             name,
             role,
             offical,
         } = version;
+        if *offical {
+            let report = self.lint_template(version, bytes)?;
+            if !report.passed() {
+                return Err(anyhow::anyhow!(serde_json::to_string(&report.issues)?));
+            }
+        }
         let base_directory = self.base_directory(*offical);
-        let filename = format!("{}.{}{}", role, name, TEMPLATE_EXTENTION);
+        let filename = format!("{}.{}.{}", role, name, self.engine.extension());
         let filepath = base_directory.join(filename);
         fs::write(filepath, bytes)?;
         Ok(())
     }
 
+    /// ## load templates into one set so they can extend, include and import each other
+    ///
+    /// the set contains templates in `shared` directory, such as a company header or macros, and templates of kind.
+    /// Templates are named by their path relative to their directory, e.g. `header.sas` or `blocks/compare.sas`,
+    /// and private templates override offical ones of the same name
+    pub fn template_set(&self) -> anyhow::Result<Tera> {
+        let mut templates = vec![];
+        for offical in [true, false] {
+            for directory in [self.shared_directory(offical), self.base_directory(offical)] {
+                self.collect_templates(&directory, &directory, &mut templates)?;
+            }
+        }
+        let mut tera = Tera::default();
+        tera.add_raw_templates(templates)?;
+        Ok(tera)
+    }
+
+    fn collect_templates(
+        &self,
+        root: &Path,
+        directory: &Path,
+        templates: &mut Vec<(String, String)>,
+    ) -> anyhow::Result<()> {
+        if !directory.exists() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                self.collect_templates(root, &path, templates)?;
+                continue;
+            }
+            if path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                != Some(self.engine.extension().into())
+            {
+                continue;
+            }
+            let name = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<String>>()
+                .join("/");
+            templates.push((name, fs::read_to_string(&path)?));
+        }
+        Ok(())
+    }
+
+    fn shared_directory(&self, offical: bool) -> PathBuf {
+        let root = if offical {
+            &self.offical_path
+        } else {
+            &self.private_path
+        };
+        match self.engine.template_directory() {
+            Some(engine) => root.join(engine).join(SHARED_TEMPLATE),
+            None => root.join(SHARED_TEMPLATE),
+        }
+    }
+
     fn base_directory(&self, offical: bool) -> PathBuf {
         let kind = match self.kind {
-            Kind::SDTM => SDTM_TEMPLATE,
-            Kind::ADAM => ADAM_TEMPLATE,
-            Kind::TFL => TFL_TEMPLATE,
+            Some(Kind::SDTM) => SDTM_TEMPLATE,
+            Some(Kind::ADAM) => ADAM_TEMPLATE,
+            Some(Kind::TFL) => TFL_TEMPLATE,
+            None => STUDY_TEMPLATE,
         };
 
-        if offical {
-            self.offical_path.join(kind)
+        let root = if offical {
+            &self.offical_path
         } else {
-            self.private_path.join(kind)
+            &self.private_path
+        };
+        match self.engine.template_directory() {
+            Some(engine) => root.join(engine).join(kind),
+            None => root.join(kind),
         }
     }
 
@@ -123,7 +240,7 @@ impl VersionManager {
     }
 
     fn filename_to_version(&self, filename: &str, offical: bool) -> Option<Version> {
-        if !filename.ends_with(TEMPLATE_EXTENTION) {
+        if !filename.ends_with(&format!(".{}", self.engine.extension())) {
             None
         } else {
             let filename = filename.split(".").collect::<Vec<&str>>();
@@ -156,6 +273,7 @@ mod tests {
             offical_path,
             private_path,
             kind,
+            engine: Engine::SAS,
         });
         let templates = manager.list_templates()?;
         assert_eq!(5, templates.len());
@@ -178,4 +296,145 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_r_templates() -> anyhow::Result<()> {
+        let offical = tempfile::tempdir()?;
+        let private = tempfile::tempdir()?;
+        let manager = VersionManager::new(&VersionManagerParam {
+            offical_path: offical.path(),
+            private_path: private.path(),
+            kind: Kind::ADAM,
+            engine: Engine::R,
+        });
+        assert_eq!(0, manager.list_templates()?.len());
+        let ver = Version {
+            name: "v1".into(),
+            role: "qc".into(),
+            offical: true,
+        };
+        manager.save_template(&ver, b"# {{ item.name }}")?;
+        assert!(offical
+            .path()
+            .join("r")
+            .join("adam")
+            .join("qc.v1.R")
+            .exists());
+        let templates = manager.list_templates()?;
+        assert_eq!(1, templates.len());
+        assert_eq!("v1", templates[0].name);
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_set() -> anyhow::Result<()> {
+        let offical = tempfile::tempdir()?;
+        let private = tempfile::tempdir()?;
+        let manager = VersionManager::new(&VersionManagerParam {
+            offical_path: offical.path(),
+            private_path: private.path(),
+            kind: Kind::SDTM,
+            engine: Engine::SAS,
+        });
+        let shared = offical.path().join(SHARED_TEMPLATE);
+        fs::create_dir_all(shared.join("blocks"))?;
+        fs::write(shared.join("header.sas"), "offical header")?;
+        fs::write(shared.join("blocks").join("end.sas"), "end")?;
+        fs::write(shared.join("notes.txt"), "not a template")?;
+        fs::create_dir_all(private.path().join(SHARED_TEMPLATE))?;
+        fs::write(
+            private.path().join(SHARED_TEMPLATE).join("header.sas"),
+            "private header",
+        )?;
+        manager.list_templates()?;
+        fs::write(offical.path().join("sdtm").join("dev.v1.sas"), "dev")?;
+
+        let tera = manager.template_set()?;
+        let mut names = tera.get_template_names().collect::<Vec<&str>>();
+        names.sort();
+        assert_eq!(vec!["blocks/end.sas", "dev.v1.sas", "header.sas"], names);
+        assert_eq!(
+            "private header",
+            tera.render("header.sas", &tera::Context::new())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_template() -> anyhow::Result<()> {
+        let offical = tempfile::tempdir()?;
+        let private = tempfile::tempdir()?;
+        let mut manager = VersionManager::new(&VersionManagerParam {
+            offical_path: offical.path(),
+            private_path: private.path(),
+            kind: Kind::TFL,
+            engine: Engine::SAS,
+        });
+        manager.set_lint_option(LintOption {
+            required_sections: vec!["Program Name".into()],
+        });
+        manager.list_templates()?;
+        let shared = offical.path().join(SHARED_TEMPLATE);
+        fs::create_dir_all(&shared)?;
+        fs::write(
+            shared.join("header.sas"),
+            "/* Program Name: {{ item.name }} */",
+        )?;
+
+        let ver = Version {
+            name: "v1".into(),
+            role: "dev".into(),
+            offical: true,
+        };
+        let report = manager.lint_template(
+            &ver,
+            b"{% include \"header.sas\" %}\n%let out={{ item.output.number }};",
+        )?;
+        assert!(report.passed(), "{:?}", report.issues);
+
+        let sdtm = VersionManager::new(&VersionManagerParam {
+            offical_path: offical.path(),
+            private_path: private.path(),
+            kind: Kind::SDTM,
+            engine: Engine::SAS,
+        });
+        sdtm.list_templates()?;
+        let compare = b"proc compare base={{ item.compare.base }};";
+        assert!(!sdtm.lint_template(&ver, compare)?.passed());
+        assert!(sdtm
+            .lint_template(
+                &ver,
+                b"{% if item.compare %}{{ item.compare.base }}{% endif %}"
+            )?
+            .passed());
+        assert!(!sdtm
+            .lint_template(&ver, b"{{ item.output.number }}")?
+            .passed());
+        let qc = Version {
+            role: "qc".into(),
+            ..ver.clone()
+        };
+        assert!(sdtm.lint_template(&qc, compare)?.passed());
+        assert!(manager
+            .save_template(&ver, b"{% include \"header.sas\" %}{{ item.titel }}")
+            .is_err());
+        assert!(manager.save_template(&ver, b"{{ item.name }}").is_err());
+        let private_ver = Version {
+            offical: false,
+            ..ver
+        };
+        manager.save_template(&private_ver, b"{{ item.name }}")?;
+
+        let study = VersionManager::study(offical.path(), private.path(), Engine::SAS);
+        let report = study.lint_template(
+            &Version {
+                name: "v1".into(),
+                role: "setup".into(),
+                offical: true,
+            },
+            b"{% for lib in item.libraries %}libname {{ lib.name }} \"{{ lib.path }}\";{% endfor %}{{ item.trial }}",
+        )?;
+        assert!(report.passed(), "{:?}", report.issues);
+        Ok(())
+    }
 }